@@ -0,0 +1,381 @@
+//! Communications Device Class (CDC) support.
+//!
+//! A CDC Abstract Control Model (ACM) function, the usual USB serial port, is
+//! made up of a communication interface (interface class 2, subclass 2) that
+//! accepts the class-specific requests and sends notifications on an
+//! interrupt endpoint, and a data interface (interface class 10) with a pair
+//! of bulk endpoints that carry the serial data. The two are linked by the
+//! union functional descriptor following the communication interface
+//! descriptor, and can be found with [`find_acm_interfaces`].
+//!
+//! The functional descriptors of a CDC function are parsed by
+//! [`FunctionalDescriptor::new`], or found in the descriptors of an interface
+//! by [`functional_descriptors`].
+//!
+//! The class-specific requests are available as methods on
+//! [`Interface`][crate::Interface]:
+//! [`cdc_set_line_coding`][crate::Interface::cdc_set_line_coding],
+//! [`cdc_get_line_coding`][crate::Interface::cdc_get_line_coding],
+//! [`cdc_set_control_line_state`][crate::Interface::cdc_set_control_line_state], and
+//! [`cdc_send_break`][crate::Interface::cdc_send_break]. [`CdcAcm`] wraps
+//! both interfaces as a serial port implementing `AsyncRead` and `AsyncWrite`.
+
+use crate::descriptors::Configuration;
+
+mod functional;
+pub use functional::{
+    functional_descriptors, AcmDescriptor, CallManagementDescriptor, EthernetNetworkingDescriptor,
+    FunctionalDescriptor, HeaderDescriptor, MbimDescriptor, NcmDescriptor, UnionDescriptor,
+};
+
+mod serial;
+pub use serial::CdcAcm;
+
+/// Interface class of a CDC communication interface.
+pub const CLASS_COMMUNICATIONS: u8 = 0x02;
+
+/// Interface class of a CDC data interface.
+pub const CLASS_DATA: u8 = 0x0A;
+
+/// Communication interface subclass of the Abstract Control Model.
+pub const SUBCLASS_ACM: u8 = 0x02;
+
+const DESCRIPTOR_TYPE_CS_INTERFACE: u8 = 0x24;
+
+pub(crate) const REQUEST_SET_LINE_CODING: u8 = 0x20;
+pub(crate) const REQUEST_GET_LINE_CODING: u8 = 0x21;
+pub(crate) const REQUEST_SET_CONTROL_LINE_STATE: u8 = 0x22;
+pub(crate) const REQUEST_SEND_BREAK: u8 = 0x23;
+
+const NOTIFICATION_REQUEST_TYPE: u8 = 0xA1;
+const NOTIFICATION_SERIAL_STATE: u8 = 0x20;
+const NOTIFICATION_HEADER_LEN: usize = 8;
+
+pub(crate) const LINE_CODING_LEN: usize = 7;
+
+/// Interface numbers of a CDC-ACM function, found by [`find_acm_interfaces`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AcmInterfaces {
+    /// Interface number of the communication interface, which receives the
+    /// class-specific requests and has the notification endpoint.
+    pub communication: u8,
+
+    /// Interface number of the data interface, which has the bulk endpoints.
+    pub data: u8,
+}
+
+/// Find the CDC-ACM functions of a configuration.
+///
+/// The data interface of each ACM communication interface is taken from its
+/// union functional descriptor. Devices that omit the union descriptor are
+/// assumed to follow the communication interface with its data interface,
+/// as Linux does.
+pub fn find_acm_interfaces(config: &Configuration<'_>) -> Vec<AcmInterfaces> {
+    let is_data_interface = |number: u8| {
+        config
+            .interface_alt_settings()
+            .any(|alt| alt.interface_number() == number && alt.class() == CLASS_DATA)
+    };
+
+    config
+        .interface_alt_settings()
+        .filter(|alt| {
+            alt.alternate_setting() == 0
+                && alt.class() == CLASS_COMMUNICATIONS
+                && alt.subclass() == SUBCLASS_ACM
+        })
+        .filter_map(|alt| {
+            let communication = alt.interface_number();
+
+            let union_data = functional_descriptors(&alt)
+                .find_map(|d| match d {
+                    FunctionalDescriptor::Union(union) => Some(union),
+                    _ => None,
+                })
+                .and_then(|union| union.subordinate_interfaces().next());
+
+            let data = union_data.or_else(|| {
+                let next = communication.checked_add(1)?;
+                is_data_interface(next).then_some(next)
+            })?;
+
+            Some(AcmInterfaces {
+                communication,
+                data,
+            })
+        })
+        .collect()
+}
+
+/// Number of stop bits in [`LineCoding`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum StopBits {
+    /// 1 stop bit.
+    One = 0,
+
+    /// 1.5 stop bits.
+    OnePointFive = 1,
+
+    /// 2 stop bits.
+    Two = 2,
+}
+
+/// Parity in [`LineCoding`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Parity {
+    /// No parity bit.
+    None = 0,
+
+    /// Odd parity.
+    Odd = 1,
+
+    /// Even parity.
+    Even = 2,
+
+    /// Parity bit always 1.
+    Mark = 3,
+
+    /// Parity bit always 0.
+    Space = 4,
+}
+
+/// Baud rate and character format of a CDC-ACM serial port, set with
+/// `SET_LINE_CODING` and read with `GET_LINE_CODING`.
+///
+/// The default is 115200 baud, 8 data bits, no parity and 1 stop bit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LineCoding {
+    /// Data terminal rate, in bits per second.
+    #[doc(alias = "dwDTERate")]
+    pub baud_rate: u32,
+
+    /// Number of stop bits.
+    #[doc(alias = "bCharFormat")]
+    pub stop_bits: StopBits,
+
+    /// Parity.
+    #[doc(alias = "bParityType")]
+    pub parity: Parity,
+
+    /// Number of data bits: 5, 6, 7, 8 or 16.
+    #[doc(alias = "bDataBits")]
+    pub data_bits: u8,
+}
+
+impl LineCoding {
+    /// Parse the response to a `GET_LINE_CODING` request.
+    ///
+    /// Returns `None` if the buffer is too short or the stop bits or parity
+    /// fields have an unknown value.
+    pub fn from_bytes(buf: &[u8]) -> Option<LineCoding> {
+        let buf = buf.get(..LINE_CODING_LEN)?;
+
+        let stop_bits = match buf[4] {
+            0 => StopBits::One,
+            1 => StopBits::OnePointFive,
+            2 => StopBits::Two,
+            _ => return None,
+        };
+
+        let parity = match buf[5] {
+            0 => Parity::None,
+            1 => Parity::Odd,
+            2 => Parity::Even,
+            3 => Parity::Mark,
+            4 => Parity::Space,
+            _ => return None,
+        };
+
+        Some(LineCoding {
+            baud_rate: u32::from_le_bytes(buf[0..4].try_into().unwrap()),
+            stop_bits,
+            parity,
+            data_bits: buf[6],
+        })
+    }
+
+    /// Serialize as the data of a `SET_LINE_CODING` request.
+    pub fn to_bytes(&self) -> [u8; LINE_CODING_LEN] {
+        let [b0, b1, b2, b3] = self.baud_rate.to_le_bytes();
+        [
+            b0,
+            b1,
+            b2,
+            b3,
+            self.stop_bits as u8,
+            self.parity as u8,
+            self.data_bits,
+        ]
+    }
+}
+
+impl Default for LineCoding {
+    fn default() -> Self {
+        LineCoding {
+            baud_rate: 115200,
+            stop_bits: StopBits::One,
+            parity: Parity::None,
+            data_bits: 8,
+        }
+    }
+}
+
+/// State of the serial port signals and errors, sent by the device in a
+/// `SERIAL_STATE` notification.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SerialState(u16);
+
+impl SerialState {
+    /// Parse a notification received on the interrupt endpoint of a CDC-ACM
+    /// communication interface.
+    ///
+    /// Returns `None` if it is not a `SERIAL_STATE` notification.
+    pub fn from_notification(buf: &[u8]) -> Option<SerialState> {
+        if buf.len() < NOTIFICATION_HEADER_LEN + 2
+            || buf[0] != NOTIFICATION_REQUEST_TYPE
+            || buf[1] != NOTIFICATION_SERIAL_STATE
+            || u16::from_le_bytes([buf[6], buf[7]]) < 2
+        {
+            return None;
+        }
+        Some(SerialState(u16::from_le_bytes([buf[8], buf[9]])))
+    }
+
+    /// The raw `UART State` bitmap.
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    /// `bRxCarrier`: Data Carrier Detect (DCD) is asserted.
+    #[doc(alias = "bRxCarrier")]
+    pub fn dcd(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+
+    /// `bTxCarrier`: Data Set Ready (DSR) is asserted.
+    #[doc(alias = "bTxCarrier")]
+    pub fn dsr(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    /// `bBreak`: A break was detected.
+    #[doc(alias = "bBreak")]
+    pub fn break_detected(&self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+
+    /// `bRingSignal`: A ring signal was detected.
+    #[doc(alias = "bRingSignal")]
+    pub fn ring(&self) -> bool {
+        self.0 & (1 << 3) != 0
+    }
+
+    /// `bFraming`: A framing error occurred.
+    #[doc(alias = "bFraming")]
+    pub fn framing_error(&self) -> bool {
+        self.0 & (1 << 4) != 0
+    }
+
+    /// `bParity`: A parity error occurred.
+    #[doc(alias = "bParity")]
+    pub fn parity_error(&self) -> bool {
+        self.0 & (1 << 5) != 0
+    }
+
+    /// `bOverRun`: Received data was lost because the device's buffer
+    /// overflowed.
+    #[doc(alias = "bOverRun")]
+    pub fn overrun(&self) -> bool {
+        self.0 & (1 << 6) != 0
+    }
+}
+
+impl std::fmt::Debug for SerialState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SerialState")
+            .field("dcd", &self.dcd())
+            .field("dsr", &self.dsr())
+            .field("break_detected", &self.break_detected())
+            .field("ring", &self.ring())
+            .field("framing_error", &self.framing_error())
+            .field("parity_error", &self.parity_error())
+            .field("overrun", &self.overrun())
+            .finish()
+    }
+}
+
+#[test]
+fn test_line_coding() {
+    let coding = LineCoding {
+        baud_rate: 9600,
+        stop_bits: StopBits::Two,
+        parity: Parity::Even,
+        data_bits: 7,
+    };
+    let buf = coding.to_bytes();
+    assert_eq!(buf, [0x80, 0x25, 0x00, 0x00, 0x02, 0x02, 0x07]);
+    assert_eq!(LineCoding::from_bytes(&buf), Some(coding));
+
+    assert_eq!(LineCoding::from_bytes(&buf[..6]), None);
+    assert_eq!(
+        LineCoding::from_bytes(&[0x80, 0x25, 0x00, 0x00, 0x00, 0x05, 0x08]),
+        None
+    );
+}
+
+#[test]
+fn test_serial_state() {
+    let state = SerialState::from_notification(&[
+        0xa1, 0x20, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03, 0x00,
+    ])
+    .unwrap();
+    assert_eq!(state.bits(), 0x0003);
+    assert!(state.dcd() && state.dsr());
+    assert!(!state.break_detected() && !state.overrun());
+
+    // NETWORK_CONNECTION
+    assert!(
+        SerialState::from_notification(&[0xa1, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]).is_none()
+    );
+}
+
+#[test]
+fn test_find_acm_interfaces() {
+    use crate::descriptors::{ConfigurationBuilder, InterfaceBuilder};
+
+    let buf = ConfigurationBuilder::new(1)
+        .interface(
+            InterfaceBuilder::new(0, 0)
+                .class(CLASS_COMMUNICATIONS)
+                .subclass(SUBCLASS_ACM)
+                .descriptor(&[0x05, 0x24, 0x00, 0x10, 0x01])
+                .descriptor(&[0x05, 0x24, 0x06, 0x00, 0x03]),
+        )
+        .interface(InterfaceBuilder::new(1, 0).class(0xff))
+        .interface(InterfaceBuilder::new(3, 0).class(CLASS_DATA))
+        // No union descriptor
+        .interface(
+            InterfaceBuilder::new(4, 0)
+                .class(CLASS_COMMUNICATIONS)
+                .subclass(SUBCLASS_ACM),
+        )
+        .interface(InterfaceBuilder::new(5, 0).class(CLASS_DATA))
+        .build();
+    let config = Configuration::new(&buf);
+
+    assert_eq!(
+        find_acm_interfaces(&config),
+        [
+            AcmInterfaces {
+                communication: 0,
+                data: 3
+            },
+            AcmInterfaces {
+                communication: 4,
+                data: 5
+            },
+        ]
+    );
+}
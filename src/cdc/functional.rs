@@ -0,0 +1,524 @@
+//! CDC functional descriptors.
+
+use std::fmt::Debug;
+
+use super::DESCRIPTOR_TYPE_CS_INTERFACE;
+use crate::descriptors::{Descriptor, InterfaceAltSetting};
+
+const SUBTYPE_HEADER: u8 = 0x00;
+const SUBTYPE_CALL_MANAGEMENT: u8 = 0x01;
+const SUBTYPE_ACM: u8 = 0x02;
+const SUBTYPE_UNION: u8 = 0x06;
+const SUBTYPE_ETHERNET_NETWORKING: u8 = 0x0F;
+const SUBTYPE_NCM: u8 = 0x1A;
+const SUBTYPE_MBIM: u8 = 0x1B;
+
+const DESCRIPTOR_LEN_HEADER: usize = 5;
+const DESCRIPTOR_LEN_CALL_MANAGEMENT: usize = 5;
+const DESCRIPTOR_LEN_ACM: usize = 4;
+const DESCRIPTOR_LEN_UNION: usize = 5;
+const DESCRIPTOR_LEN_ETHERNET_NETWORKING: usize = 13;
+const DESCRIPTOR_LEN_NCM: usize = 6;
+const DESCRIPTOR_LEN_MBIM: usize = 12;
+
+/// Truncate `buf` to its `bFunctionLength`, and check that it is a
+/// class-specific interface descriptor of `subtype` at least `min_len` long.
+fn functional_descriptor(buf: &[u8], subtype: u8, min_len: usize) -> Option<&[u8]> {
+    let buf = buf.get(..*buf.first()? as usize)?;
+    (buf.len() >= min_len && buf[1] == DESCRIPTOR_TYPE_CS_INTERFACE && buf[2] == subtype)
+        .then_some(buf)
+}
+
+/// Iterate over the CDC functional descriptors that follow an interface
+/// descriptor.
+///
+/// Class-specific descriptors with an unknown subtype, or that are too short
+/// for their subtype, are skipped.
+pub fn functional_descriptors<'a>(
+    intf: &'a InterfaceAltSetting<'_>,
+) -> impl Iterator<Item = FunctionalDescriptor<'a>> + 'a {
+    intf.descriptors()
+        .filter_map(|desc| FunctionalDescriptor::from_descriptor(&desc))
+}
+
+/// A parsed CDC functional descriptor, a class-specific interface descriptor
+/// (`bDescriptorType` `0x24`) that describes a CDC function.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum FunctionalDescriptor<'a> {
+    /// Header functional descriptor.
+    Header(HeaderDescriptor<'a>),
+
+    /// Call management functional descriptor.
+    CallManagement(CallManagementDescriptor<'a>),
+
+    /// Abstract control management functional descriptor.
+    Acm(AcmDescriptor<'a>),
+
+    /// Union functional descriptor.
+    Union(UnionDescriptor<'a>),
+
+    /// Ethernet networking functional descriptor.
+    EthernetNetworking(EthernetNetworkingDescriptor<'a>),
+
+    /// NCM functional descriptor.
+    Ncm(NcmDescriptor<'a>),
+
+    /// MBIM functional descriptor.
+    Mbim(MbimDescriptor<'a>),
+}
+
+impl<'a> FunctionalDescriptor<'a> {
+    /// Parse a buffer containing a functional descriptor.
+    ///
+    /// Returns `None` if the buffer is not a class-specific interface
+    /// descriptor, has an unknown `bDescriptorSubtype`, or is too short for
+    /// its subtype.
+    pub fn new(buf: &'a [u8]) -> Option<FunctionalDescriptor<'a>> {
+        Some(match *buf.get(2)? {
+            SUBTYPE_HEADER => FunctionalDescriptor::Header(HeaderDescriptor::new(buf)?),
+            SUBTYPE_CALL_MANAGEMENT => {
+                FunctionalDescriptor::CallManagement(CallManagementDescriptor::new(buf)?)
+            }
+            SUBTYPE_ACM => FunctionalDescriptor::Acm(AcmDescriptor::new(buf)?),
+            SUBTYPE_UNION => FunctionalDescriptor::Union(UnionDescriptor::new(buf)?),
+            SUBTYPE_ETHERNET_NETWORKING => {
+                FunctionalDescriptor::EthernetNetworking(EthernetNetworkingDescriptor::new(buf)?)
+            }
+            SUBTYPE_NCM => FunctionalDescriptor::Ncm(NcmDescriptor::new(buf)?),
+            SUBTYPE_MBIM => FunctionalDescriptor::Mbim(MbimDescriptor::new(buf)?),
+            _ => return None,
+        })
+    }
+
+    /// Parse a descriptor yielded by
+    /// [`InterfaceAltSetting::descriptors`][crate::descriptors::InterfaceAltSetting::descriptors].
+    pub fn from_descriptor(desc: &Descriptor<'a>) -> Option<FunctionalDescriptor<'a>> {
+        FunctionalDescriptor::new(desc.as_bytes())
+    }
+
+    /// `bDescriptorSubtype` descriptor field.
+    #[doc(alias = "bDescriptorSubtype")]
+    pub fn subtype(&self) -> u8 {
+        self.as_bytes()[2]
+    }
+
+    /// Get the bytes of the descriptor.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        match self {
+            FunctionalDescriptor::Header(d) => d.0,
+            FunctionalDescriptor::CallManagement(d) => d.0,
+            FunctionalDescriptor::Acm(d) => d.0,
+            FunctionalDescriptor::Union(d) => d.0,
+            FunctionalDescriptor::EthernetNetworking(d) => d.0,
+            FunctionalDescriptor::Ncm(d) => d.0,
+            FunctionalDescriptor::Mbim(d) => d.0,
+        }
+    }
+}
+
+/// Header functional descriptor, which begins the functional descriptors of
+/// a CDC communication interface.
+#[derive(Clone)]
+pub struct HeaderDescriptor<'a>(&'a [u8]);
+
+impl<'a> HeaderDescriptor<'a> {
+    /// Create a `HeaderDescriptor` from a buffer containing the descriptor.
+    ///
+    /// Returns `None` if the buffer is not a header functional descriptor.
+    pub fn new(buf: &'a [u8]) -> Option<HeaderDescriptor<'a>> {
+        functional_descriptor(buf, SUBTYPE_HEADER, DESCRIPTOR_LEN_HEADER).map(HeaderDescriptor)
+    }
+
+    /// `bcdCDC` descriptor field: CDC specification version, in binary-coded decimal.
+    #[doc(alias = "bcdCDC")]
+    pub fn cdc_version(&self) -> u16 {
+        u16::from_le_bytes([self.0[3], self.0[4]])
+    }
+}
+
+impl<'a> Debug for HeaderDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeaderDescriptor")
+            .field("cdc_version", &format_args!("0x{:04X}", self.cdc_version()))
+            .finish()
+    }
+}
+
+/// Call management functional descriptor, describing how a CDC-ACM function
+/// handles call management.
+#[derive(Clone)]
+pub struct CallManagementDescriptor<'a>(&'a [u8]);
+
+impl<'a> CallManagementDescriptor<'a> {
+    /// Create a `CallManagementDescriptor` from a buffer containing the descriptor.
+    ///
+    /// Returns `None` if the buffer is not a call management functional descriptor.
+    pub fn new(buf: &'a [u8]) -> Option<CallManagementDescriptor<'a>> {
+        functional_descriptor(buf, SUBTYPE_CALL_MANAGEMENT, DESCRIPTOR_LEN_CALL_MANAGEMENT)
+            .map(CallManagementDescriptor)
+    }
+
+    /// `bmCapabilities` descriptor field: Bit 0 is set if the device handles
+    /// call management itself, and bit 1 if it can do so over the data
+    /// interface.
+    #[doc(alias = "bmCapabilities")]
+    pub fn capabilities(&self) -> u8 {
+        self.0[3]
+    }
+
+    /// `bDataInterface` descriptor field: Interface number of the data
+    /// interface used for call management.
+    #[doc(alias = "bDataInterface")]
+    pub fn data_interface(&self) -> u8 {
+        self.0[4]
+    }
+}
+
+impl<'a> Debug for CallManagementDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallManagementDescriptor")
+            .field(
+                "capabilities",
+                &format_args!("0x{:02X}", self.capabilities()),
+            )
+            .field("data_interface", &self.data_interface())
+            .finish()
+    }
+}
+
+/// Abstract control management functional descriptor, listing the requests
+/// and notifications supported by a CDC-ACM communication interface.
+#[derive(Clone)]
+pub struct AcmDescriptor<'a>(&'a [u8]);
+
+impl<'a> AcmDescriptor<'a> {
+    /// Create an `AcmDescriptor` from a buffer containing the descriptor.
+    ///
+    /// Returns `None` if the buffer is not an abstract control management
+    /// functional descriptor.
+    pub fn new(buf: &'a [u8]) -> Option<AcmDescriptor<'a>> {
+        functional_descriptor(buf, SUBTYPE_ACM, DESCRIPTOR_LEN_ACM).map(AcmDescriptor)
+    }
+
+    /// `bmCapabilities` descriptor field.
+    #[doc(alias = "bmCapabilities")]
+    pub fn capabilities(&self) -> u8 {
+        self.0[3]
+    }
+
+    /// Supports the `SET_COMM_FEATURE`, `CLEAR_COMM_FEATURE` and
+    /// `GET_COMM_FEATURE` requests.
+    pub fn supports_comm_feature(&self) -> bool {
+        self.capabilities() & (1 << 0) != 0
+    }
+
+    /// Supports the `SET_LINE_CODING`, `GET_LINE_CODING` and
+    /// `SET_CONTROL_LINE_STATE` requests and the `SERIAL_STATE` notification.
+    pub fn supports_line_coding(&self) -> bool {
+        self.capabilities() & (1 << 1) != 0
+    }
+
+    /// Supports the `SEND_BREAK` request.
+    pub fn supports_send_break(&self) -> bool {
+        self.capabilities() & (1 << 2) != 0
+    }
+
+    /// Supports the `NETWORK_CONNECTION` notification.
+    pub fn supports_network_connection(&self) -> bool {
+        self.capabilities() & (1 << 3) != 0
+    }
+}
+
+impl<'a> Debug for AcmDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AcmDescriptor")
+            .field(
+                "capabilities",
+                &format_args!("0x{:02X}", self.capabilities()),
+            )
+            .finish()
+    }
+}
+
+/// Union functional descriptor, grouping the interfaces of a CDC function.
+#[derive(Clone)]
+pub struct UnionDescriptor<'a>(&'a [u8]);
+
+impl<'a> UnionDescriptor<'a> {
+    /// Create a `UnionDescriptor` from a buffer containing the descriptor.
+    ///
+    /// Returns `None` if the buffer is not a union functional descriptor
+    /// with at least one subordinate interface.
+    pub fn new(buf: &'a [u8]) -> Option<UnionDescriptor<'a>> {
+        functional_descriptor(buf, SUBTYPE_UNION, DESCRIPTOR_LEN_UNION).map(UnionDescriptor)
+    }
+
+    /// `bControlInterface` descriptor field: Interface number of the
+    /// communication interface that controls the function.
+    #[doc(alias = "bControlInterface")]
+    pub fn control_interface(&self) -> u8 {
+        self.0[3]
+    }
+
+    /// `bSubordinateInterface` descriptor fields: Interface numbers of the
+    /// other interfaces of the function, such as the data interface.
+    #[doc(alias = "bSubordinateInterface0")]
+    pub fn subordinate_interfaces(&self) -> impl Iterator<Item = u8> + 'a {
+        self.0[4..].iter().copied()
+    }
+}
+
+impl<'a> Debug for UnionDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnionDescriptor")
+            .field("control_interface", &self.control_interface())
+            .field(
+                "subordinate_interfaces",
+                &self.subordinate_interfaces().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Ethernet networking functional descriptor, describing the Ethernet
+/// interface of an ECM, NCM or EEM function.
+#[derive(Clone)]
+pub struct EthernetNetworkingDescriptor<'a>(&'a [u8]);
+
+impl<'a> EthernetNetworkingDescriptor<'a> {
+    /// Create an `EthernetNetworkingDescriptor` from a buffer containing the descriptor.
+    ///
+    /// Returns `None` if the buffer is not an Ethernet networking functional
+    /// descriptor.
+    pub fn new(buf: &'a [u8]) -> Option<EthernetNetworkingDescriptor<'a>> {
+        functional_descriptor(
+            buf,
+            SUBTYPE_ETHERNET_NETWORKING,
+            DESCRIPTOR_LEN_ETHERNET_NETWORKING,
+        )
+        .map(EthernetNetworkingDescriptor)
+    }
+
+    /// `iMACAddress` descriptor field: Index of the string descriptor
+    /// containing the MAC address as 12 hexadecimal digits.
+    #[doc(alias = "iMACAddress")]
+    pub fn mac_address_string_index(&self) -> u8 {
+        self.0[3]
+    }
+
+    /// `bmEthernetStatistics` descriptor field: Bitmap of the Ethernet
+    /// statistics collected by the device.
+    #[doc(alias = "bmEthernetStatistics")]
+    pub fn ethernet_statistics(&self) -> u32 {
+        u32::from_le_bytes([self.0[4], self.0[5], self.0[6], self.0[7]])
+    }
+
+    /// `wMaxSegmentSize` descriptor field: Maximum segment size, usually
+    /// 1514 bytes.
+    #[doc(alias = "wMaxSegmentSize")]
+    pub fn max_segment_size(&self) -> u16 {
+        u16::from_le_bytes([self.0[8], self.0[9]])
+    }
+
+    /// `wNumberMCFilters` descriptor field: Number of multicast filters. Bit
+    /// 15 is set if the filtering is imperfect.
+    #[doc(alias = "wNumberMCFilters")]
+    pub fn number_multicast_filters(&self) -> u16 {
+        u16::from_le_bytes([self.0[10], self.0[11]])
+    }
+
+    /// `bNumberPowerFilters` descriptor field: Number of wake-up pattern filters.
+    #[doc(alias = "bNumberPowerFilters")]
+    pub fn number_power_filters(&self) -> u8 {
+        self.0[12]
+    }
+}
+
+impl<'a> Debug for EthernetNetworkingDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EthernetNetworkingDescriptor")
+            .field("mac_address_string_index", &self.mac_address_string_index())
+            .field(
+                "ethernet_statistics",
+                &format_args!("0x{:08X}", self.ethernet_statistics()),
+            )
+            .field("max_segment_size", &self.max_segment_size())
+            .field("number_multicast_filters", &self.number_multicast_filters())
+            .field("number_power_filters", &self.number_power_filters())
+            .finish()
+    }
+}
+
+/// NCM functional descriptor, listing the optional requests supported by a
+/// Network Control Model function.
+#[derive(Clone)]
+pub struct NcmDescriptor<'a>(&'a [u8]);
+
+impl<'a> NcmDescriptor<'a> {
+    /// Create an `NcmDescriptor` from a buffer containing the descriptor.
+    ///
+    /// Returns `None` if the buffer is not an NCM functional descriptor.
+    pub fn new(buf: &'a [u8]) -> Option<NcmDescriptor<'a>> {
+        functional_descriptor(buf, SUBTYPE_NCM, DESCRIPTOR_LEN_NCM).map(NcmDescriptor)
+    }
+
+    /// `bcdNcmVersion` descriptor field: NCM specification version, in binary-coded decimal.
+    #[doc(alias = "bcdNcmVersion")]
+    pub fn ncm_version(&self) -> u16 {
+        u16::from_le_bytes([self.0[3], self.0[4]])
+    }
+
+    /// `bmNetworkCapabilities` descriptor field.
+    #[doc(alias = "bmNetworkCapabilities")]
+    pub fn network_capabilities(&self) -> u8 {
+        self.0[5]
+    }
+}
+
+impl<'a> Debug for NcmDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NcmDescriptor")
+            .field("ncm_version", &format_args!("0x{:04X}", self.ncm_version()))
+            .field(
+                "network_capabilities",
+                &format_args!("0x{:02X}", self.network_capabilities()),
+            )
+            .finish()
+    }
+}
+
+/// MBIM functional descriptor, describing a Mobile Broadband Interface
+/// Model function.
+#[derive(Clone)]
+pub struct MbimDescriptor<'a>(&'a [u8]);
+
+impl<'a> MbimDescriptor<'a> {
+    /// Create an `MbimDescriptor` from a buffer containing the descriptor.
+    ///
+    /// Returns `None` if the buffer is not an MBIM functional descriptor.
+    pub fn new(buf: &'a [u8]) -> Option<MbimDescriptor<'a>> {
+        functional_descriptor(buf, SUBTYPE_MBIM, DESCRIPTOR_LEN_MBIM).map(MbimDescriptor)
+    }
+
+    /// `bcdMBIMVersion` descriptor field: MBIM specification version, in binary-coded decimal.
+    #[doc(alias = "bcdMBIMVersion")]
+    pub fn mbim_version(&self) -> u16 {
+        u16::from_le_bytes([self.0[3], self.0[4]])
+    }
+
+    /// `wMaxControlMessage` descriptor field: Largest control message
+    /// supported by the function.
+    #[doc(alias = "wMaxControlMessage")]
+    pub fn max_control_message(&self) -> u16 {
+        u16::from_le_bytes([self.0[5], self.0[6]])
+    }
+
+    /// `bNumberFilters` descriptor field: Number of packet filters.
+    #[doc(alias = "bNumberFilters")]
+    pub fn number_filters(&self) -> u8 {
+        self.0[7]
+    }
+
+    /// `bMaxFilterSize` descriptor field: Largest packet filter, in bytes.
+    #[doc(alias = "bMaxFilterSize")]
+    pub fn max_filter_size(&self) -> u8 {
+        self.0[8]
+    }
+
+    /// `wMaxSegmentSize` descriptor field: Largest IP packet or Ethernet frame.
+    #[doc(alias = "wMaxSegmentSize")]
+    pub fn max_segment_size(&self) -> u16 {
+        u16::from_le_bytes([self.0[9], self.0[10]])
+    }
+
+    /// `bmNetworkCapabilities` descriptor field.
+    #[doc(alias = "bmNetworkCapabilities")]
+    pub fn network_capabilities(&self) -> u8 {
+        self.0[11]
+    }
+}
+
+impl<'a> Debug for MbimDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MbimDescriptor")
+            .field(
+                "mbim_version",
+                &format_args!("0x{:04X}", self.mbim_version()),
+            )
+            .field("max_control_message", &self.max_control_message())
+            .field("number_filters", &self.number_filters())
+            .field("max_filter_size", &self.max_filter_size())
+            .field("max_segment_size", &self.max_segment_size())
+            .field(
+                "network_capabilities",
+                &format_args!("0x{:02X}", self.network_capabilities()),
+            )
+            .finish()
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_functional_descriptors() {
+    use crate::descriptors::{Configuration, ConfigurationBuilder, InterfaceBuilder};
+
+    let buf = ConfigurationBuilder::new(1)
+        .interface(
+            InterfaceBuilder::new(0, 0)
+                .class(0x02)
+                .subclass(0x0d)
+                .descriptor(&[0x05, 0x24, 0x00, 0x10, 0x01])
+                .descriptor(&[0x05, 0x24, 0x06, 0x00, 0x01])
+                .descriptor(&[0x0d, 0x24, 0x0f, 0x04, 0x00, 0x00, 0x00, 0x00, 0xea, 0x05, 0x00, 0x00, 0x00])
+                .descriptor(&[0x06, 0x24, 0x1a, 0x00, 0x01, 0x21])
+                .descriptor(&[0x0c, 0x24, 0x1b, 0x00, 0x01, 0x00, 0x10, 0x20, 0x80, 0xdc, 0x05, 0x20])
+                // Unknown subtype
+                .descriptor(&[0x05, 0x24, 0x15, 0x00, 0x01])
+                // Too short for a call management descriptor
+                .descriptor(&[0x04, 0x24, 0x01, 0x00])
+                .descriptor(&[0x05, 0x24, 0x01, 0x03, 0x01])
+                .descriptor(&[0x04, 0x24, 0x02, 0x06]),
+        )
+        .build();
+    let config = Configuration::new(&buf);
+    let alt = config.interface_alt_settings().next().unwrap();
+
+    let descriptors: Vec<_> = functional_descriptors(&alt).collect();
+    assert_eq!(descriptors.iter().map(|d| d.subtype()).collect::<Vec<_>>(), [0x00, 0x06, 0x0f, 0x1a, 0x1b, 0x01, 0x02]);
+
+    let FunctionalDescriptor::Header(header) = &descriptors[0] else { panic!() };
+    assert_eq!(header.cdc_version(), 0x0110);
+
+    let FunctionalDescriptor::Union(union) = &descriptors[1] else { panic!() };
+    assert_eq!(union.control_interface(), 0);
+    assert_eq!(union.subordinate_interfaces().collect::<Vec<_>>(), [1]);
+
+    let FunctionalDescriptor::EthernetNetworking(ethernet) = &descriptors[2] else { panic!() };
+    assert_eq!(ethernet.mac_address_string_index(), 4);
+    assert_eq!(ethernet.max_segment_size(), 1514);
+    assert_eq!(ethernet.number_multicast_filters(), 0);
+
+    let FunctionalDescriptor::Ncm(ncm) = &descriptors[3] else { panic!() };
+    assert_eq!(ncm.ncm_version(), 0x0100);
+    assert_eq!(ncm.network_capabilities(), 0x21);
+
+    let FunctionalDescriptor::Mbim(mbim) = &descriptors[4] else { panic!() };
+    assert_eq!(mbim.mbim_version(), 0x0100);
+    assert_eq!(mbim.max_control_message(), 0x1000);
+    assert_eq!(mbim.number_filters(), 0x20);
+    assert_eq!(mbim.max_filter_size(), 0x80);
+    assert_eq!(mbim.max_segment_size(), 1500);
+    assert_eq!(mbim.network_capabilities(), 0x20);
+
+    let FunctionalDescriptor::CallManagement(call_management) = &descriptors[5] else { panic!() };
+    assert_eq!(call_management.capabilities(), 0x03);
+    assert_eq!(call_management.data_interface(), 1);
+
+    let FunctionalDescriptor::Acm(acm) = &descriptors[6] else { panic!() };
+    assert!(acm.supports_line_coding() && acm.supports_send_break());
+    assert!(!acm.supports_comm_feature() && !acm.supports_network_connection());
+
+    assert!(UnionDescriptor::new(&[0x04, 0x24, 0x06, 0x00]).is_none());
+    assert!(HeaderDescriptor::new(&[0x05, 0x21, 0x00, 0x10, 0x01]).is_none());
+}
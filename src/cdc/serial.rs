@@ -0,0 +1,357 @@
+//! CDC-ACM serial port using the bulk endpoints of the data interface.
+
+use std::{
+    fmt::Debug,
+    future::{poll_fn, Future},
+    io::{self, ErrorKind},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+use super::{find_acm_interfaces, LineCoding, SerialState};
+use crate::{
+    transfer::{Direction, EndpointType, Queue, RequestBuffer, TransferError},
+    Device, Error, Interface,
+};
+
+/// Number of transfers kept pending on each endpoint.
+const NUM_TRANSFERS: usize = 4;
+
+/// Size of the transfers submitted on the bulk IN endpoint.
+const READ_TRANSFER_LEN: usize = 4096;
+
+/// Largest notification read from the interrupt endpoint.
+const NOTIFICATION_LEN: usize = 64;
+
+/// A CDC-ACM serial port.
+///
+/// Serial data is read and written through the `AsyncRead` and `AsyncWrite`
+/// implementations, which use the bulk endpoints of the data interface. The
+/// bulk IN endpoint is kept busy with several pending transfers, so that data
+/// is not lost in the device's buffer between reads.
+///
+/// Writes are buffered: `poll_write` returns once the data is submitted, and
+/// an error from a write transfer is returned by a later `poll_write` or
+/// `poll_flush`. Call `flush` to wait until the device has received the data.
+///
+/// ### Example
+///
+/// ```no_run
+/// use futures_lite::{future::block_on, AsyncReadExt, AsyncWriteExt};
+/// use nusb::cdc::{CdcAcm, LineCoding};
+/// # fn main() -> Result<(), std::io::Error> {
+/// # let di = nusb::list_devices().unwrap().next().unwrap();
+/// # let device = di.open().unwrap();
+/// let mut port = CdcAcm::open(&device)?;
+///
+/// block_on(async {
+///     port.set_line_coding(&LineCoding::default()).await?;
+///     port.set_control_line_state(true, true).await?;
+///
+///     port.write_all(b"hello\r\n").await?;
+///     port.flush().await?;
+///
+///     let mut buf = [0; 64];
+///     let len = port.read(&mut buf).await?;
+///     println!("{:?}", &buf[..len]);
+///     Ok(())
+/// })
+/// # }
+/// ```
+pub struct CdcAcm {
+    communication: Interface,
+    data: Interface,
+    notifications: Option<Queue<RequestBuffer>>,
+    notification_len: usize,
+    in_queue: Queue<RequestBuffer>,
+    in_transfer_len: usize,
+    out_queue: Queue<Vec<u8>>,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+}
+
+impl CdcAcm {
+    /// Claim the interfaces of the first CDC-ACM function in the device's
+    /// active configuration and open it as a serial port.
+    ///
+    /// The interfaces must not be bound to a kernel driver, such as Linux's
+    /// `cdc_acm`.
+    pub fn open(device: &Device) -> Result<CdcAcm, Error> {
+        let config = device.active_configuration()?;
+        let interfaces = find_acm_interfaces(&config)
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no CDC-ACM function found"))?;
+
+        let communication = device.claim_interface(interfaces.communication)?;
+        let data = device.claim_interface(interfaces.data)?;
+        CdcAcm::new(communication, data)
+    }
+
+    /// Open a serial port from claimed communication and data interfaces.
+    ///
+    /// If the bulk endpoints are not in alternate setting 0 of the data
+    /// interface, the first alternate setting that has them is selected.
+    /// Returns an error if the data interface has no pair of bulk endpoints.
+    pub fn new(communication: Interface, data: Interface) -> Result<CdcAcm, Error> {
+        let bulk_endpoint = |alt: &crate::descriptors::InterfaceAltSetting<'_>, direction| {
+            alt.endpoints()
+                .find(|ep| ep.transfer_type() == EndpointType::Bulk && ep.direction() == direction)
+                .map(|ep| (ep.address(), ep.max_packet_size()))
+        };
+
+        let (alt_setting, (in_address, in_packet_size), (out_address, _)) = data
+            .descriptors()
+            .find_map(|alt| {
+                Some((
+                    alt.alternate_setting(),
+                    bulk_endpoint(&alt, Direction::In)?,
+                    bulk_endpoint(&alt, Direction::Out)?,
+                ))
+            })
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no bulk endpoints"))?;
+
+        if alt_setting != 0 {
+            data.set_alt_setting(alt_setting)?;
+        }
+
+        let notification_endpoint = communication
+            .descriptors()
+            .find(|alt| alt.alternate_setting() == 0)
+            .and_then(|alt| {
+                alt.endpoints()
+                    .find(|ep| {
+                        ep.transfer_type() == EndpointType::Interrupt
+                            && ep.direction() == Direction::In
+                    })
+                    .map(|ep| (ep.address(), ep.max_packet_size()))
+            });
+
+        // A SERIAL_STATE notification may span several packets, so request
+        // whole packets and more than one of them if they are small.
+        let whole_packets = |len: usize, packet_size: usize| {
+            let packet_size = packet_size.max(1);
+            len.div_ceil(packet_size) * packet_size
+        };
+
+        let mut notification_len = 0;
+        let notifications = notification_endpoint.map(|(address, packet_size)| {
+            notification_len = whole_packets(NOTIFICATION_LEN, packet_size);
+            let mut queue = communication.interrupt_in_queue(address);
+            for _ in 0..NUM_TRANSFERS {
+                queue.submit(RequestBuffer::new(notification_len));
+            }
+            queue
+        });
+
+        let in_transfer_len = whole_packets(READ_TRANSFER_LEN, in_packet_size);
+        let mut in_queue = data.bulk_in_queue(in_address);
+        for _ in 0..NUM_TRANSFERS {
+            in_queue.submit(RequestBuffer::new(in_transfer_len));
+        }
+
+        let out_queue = data.bulk_out_queue(out_address);
+
+        Ok(CdcAcm {
+            communication,
+            data,
+            notifications,
+            notification_len,
+            in_queue,
+            in_transfer_len,
+            out_queue,
+            read_buf: Vec::new(),
+            read_pos: 0,
+        })
+    }
+
+    /// The claimed communication interface.
+    pub fn communication_interface(&self) -> &Interface {
+        &self.communication
+    }
+
+    /// The claimed data interface.
+    pub fn data_interface(&self) -> &Interface {
+        &self.data
+    }
+
+    /// Set the baud rate and character format.
+    pub fn set_line_coding(
+        &self,
+        line_coding: &LineCoding,
+    ) -> impl Future<Output = Result<(), TransferError>> + Send + Sync {
+        let t = self.communication.cdc_set_line_coding(line_coding);
+        async move { t.await.status }
+    }
+
+    /// Read the baud rate and character format.
+    ///
+    /// Returns an [`ErrorKind::InvalidData`] error if the device's response
+    /// is not a valid line coding.
+    pub fn line_coding(&self) -> impl Future<Output = Result<LineCoding, Error>> + Send + Sync {
+        let t = self.communication.cdc_get_line_coding();
+        async move {
+            let buf = t.await.into_result()?;
+            LineCoding::from_bytes(&buf)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid line coding"))
+        }
+    }
+
+    /// Set the DTR and RTS signals.
+    ///
+    /// Many devices only send data to the host while DTR is asserted.
+    pub fn set_control_line_state(
+        &self,
+        dtr: bool,
+        rts: bool,
+    ) -> impl Future<Output = Result<(), TransferError>> + Send + Sync {
+        let t = self.communication.cdc_set_control_line_state(dtr, rts);
+        async move { t.await.status }
+    }
+
+    /// Transmit a break for `duration` milliseconds.
+    ///
+    /// See [`Interface::cdc_send_break`].
+    pub fn send_break(
+        &self,
+        duration: u16,
+    ) -> impl Future<Output = Result<(), TransferError>> + Send + Sync {
+        let t = self.communication.cdc_send_break(duration);
+        async move { t.await.status }
+    }
+
+    /// Return a `Future` that waits for the next `SERIAL_STATE`
+    /// notification.
+    ///
+    /// This future is cancel-safe: notifications that arrive after it is
+    /// dropped are returned by the next call.
+    pub fn serial_state(
+        &mut self,
+    ) -> impl Future<Output = Result<SerialState, TransferError>> + Unpin + Send + Sync + '_ {
+        poll_fn(|cx| self.poll_serial_state(cx))
+    }
+
+    /// Get the next `SERIAL_STATE` notification if one has been received, or
+    /// register the current task for wakeup when the next notification
+    /// arrives.
+    ///
+    /// Other notifications are ignored. Returns
+    /// [`TransferError::Disconnected`] if the communication interface has no
+    /// notification endpoint.
+    pub fn poll_serial_state(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<SerialState, TransferError>> {
+        let Some(queue) = &mut self.notifications else {
+            return Poll::Ready(Err(TransferError::Disconnected));
+        };
+
+        loop {
+            if queue.pending() == 0 {
+                return Poll::Ready(Err(TransferError::Disconnected));
+            }
+
+            let completion = ready!(queue.poll_next(cx));
+            let status = completion.status;
+            let state = status
+                .is_ok()
+                .then(|| SerialState::from_notification(&completion.data))
+                .flatten();
+
+            if !matches!(status, Err(TransferError::Disconnected)) {
+                queue.submit(RequestBuffer::reuse(completion.data, self.notification_len));
+            }
+
+            status?;
+            if let Some(state) = state {
+                return Poll::Ready(Ok(state));
+            }
+        }
+    }
+}
+
+impl AsyncRead for CdcAcm {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        loop {
+            let available = &this.read_buf[this.read_pos..];
+            if !available.is_empty() || buf.is_empty() {
+                let len = available.len().min(buf.len());
+                buf[..len].copy_from_slice(&available[..len]);
+                this.read_pos += len;
+                return Poll::Ready(Ok(len));
+            }
+
+            if this.in_queue.pending() == 0 {
+                return Poll::Ready(Err(TransferError::Disconnected.into()));
+            }
+
+            // Zero-length packets complete with no data and are skipped,
+            // because returning `Ok(0)` would signal end of file.
+            let completion = ready!(this.in_queue.poll_next(cx));
+            let status = completion.status;
+            let consumed = std::mem::replace(&mut this.read_buf, completion.data);
+            this.read_pos = 0;
+
+            if !matches!(status, Err(TransferError::Disconnected)) {
+                this.in_queue
+                    .submit(RequestBuffer::reuse(consumed, this.in_transfer_len));
+            }
+
+            status?;
+        }
+    }
+}
+
+impl AsyncWrite for CdcAcm {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        while this.out_queue.pending() >= NUM_TRANSFERS {
+            ready!(this.out_queue.poll_next(cx)).status?;
+        }
+
+        this.out_queue.submit(buf.to_vec());
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        while this.out_queue.pending() > 0 {
+            ready!(this.out_queue.poll_next(cx)).status?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl Debug for CdcAcm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CdcAcm")
+            .field(
+                "communication_interface",
+                &self.communication.interface_number(),
+            )
+            .field("data_interface", &self.data.interface_number())
+            .finish_non_exhaustive()
+    }
+}
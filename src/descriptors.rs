@@ -7,16 +7,33 @@ use std::{
     fmt::{Debug, Display},
     io::ErrorKind,
     iter,
-    ops::Deref,
+    ops::{Deref, Range},
 };
 
 use log::warn;
 
 use crate::{
     transfer::{Direction, EndpointType},
-    Error,
+    Error, Speed,
 };
 
+mod builder;
+pub use builder::{
+    build_language_ids_descriptor, build_string_descriptor, BosBuilder, ConfigurationBuilder,
+    DeviceDescriptorBuilder, EndpointBuilder, InterfaceAssociationBuilder, InterfaceBuilder,
+    SuperSpeedCompanionBuilder,
+};
+
+mod owned;
+pub use owned::{OwnedConfiguration, OwnedEndpoint, OwnedInterfaceAltSetting, OwnedInterfaceGroup};
+
+mod snapshot;
+pub use snapshot::{ConfigurationSnapshot, EndpointSnapshot, InterfaceAltSettingSnapshot};
+
+mod validate;
+pub use validate::{validate_configuration, DescriptorProblem};
+
+pub(crate) const DESCRIPTOR_TYPE_DEVICE: u8 = 0x01;
 pub(crate) const DESCRIPTOR_LEN_DEVICE: u8 = 18;
 
 pub(crate) const DESCRIPTOR_TYPE_CONFIGURATION: u8 = 0x02;
@@ -30,6 +47,9 @@ pub(crate) const DESCRIPTOR_LEN_ENDPOINT: u8 = 7;
 
 pub(crate) const DESCRIPTOR_TYPE_STRING: u8 = 0x03;
 
+pub(crate) const DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION: u8 = 0x0B;
+pub(crate) const DESCRIPTOR_LEN_INTERFACE_ASSOCIATION: u8 = 8;
+
 /// USB defined language IDs for string descriptors.
 ///
 /// In practice, different language IDs are not used,
@@ -72,6 +92,11 @@ impl<'a> Descriptor<'a> {
     pub fn descriptor_type(&self) -> u8 {
         self.0[1]
     }
+
+    /// Get the bytes of the descriptor.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
 }
 
 impl<'a> Deref for Descriptor<'a> {
@@ -217,6 +242,124 @@ pub(crate) fn validate_config_descriptor(buf: &[u8]) -> Option<usize> {
     Some(total_len)
 }
 
+/// Information about a USB device from its device descriptor.
+#[derive(Clone)]
+pub struct DeviceDescriptor<'a>(&'a [u8]);
+
+impl<'a> DeviceDescriptor<'a> {
+    /// Create a `DeviceDescriptor` from a buffer beginning with a device descriptor.
+    ///
+    /// You normally obtain a `DeviceDescriptor` from a [`DeviceInfo`][crate::DeviceInfo], but this allows creating
+    /// one from your own descriptor bytes for tests.
+    ///
+    /// ### Panics
+    ///  * when the buffer is too short for a device descriptor
+    ///  * when the first descriptor is not a device descriptor
+    pub fn new(buf: &[u8]) -> DeviceDescriptor<'_> {
+        assert!(buf.len() >= DESCRIPTOR_LEN_DEVICE as usize);
+        assert!(buf[0] as usize >= DESCRIPTOR_LEN_DEVICE as usize);
+        assert!(buf[1] == DESCRIPTOR_TYPE_DEVICE);
+        DeviceDescriptor(&buf[..DESCRIPTOR_LEN_DEVICE as usize])
+    }
+
+    /// Get the bytes of the descriptor.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+descriptor_fields! {
+    impl<'a> DeviceDescriptor<'a> {
+        /// `bcdUSB` descriptor field: USB Specification Number.
+        #[doc(alias = "bcdUSB")]
+        pub fn usb_version at 2 -> u16;
+
+        /// `bDeviceClass` descriptor field: Standard device class.
+        #[doc(alias = "bDeviceClass")]
+        pub fn class at 4 -> u8;
+
+        /// `bDeviceSubClass` descriptor field: Standard subclass.
+        #[doc(alias = "bDeviceSubClass")]
+        pub fn subclass at 5 -> u8;
+
+        /// `bDeviceProtocol` descriptor field: Standard protocol.
+        #[doc(alias = "bDeviceProtocol")]
+        pub fn protocol at 6 -> u8;
+
+        /// `bMaxPacketSize0` descriptor field: Maximum packet size for endpoint 0.
+        #[doc(alias = "bMaxPacketSize0")]
+        pub fn max_packet_size_0 at 7 -> u8;
+
+        /// `idVendor` descriptor field: Standard vendor identifier.
+        #[doc(alias = "idVendor")]
+        pub fn vendor_id at 8 -> u16;
+
+        /// `idProduct` descriptor field: Vendor-assigned product identifier.
+        #[doc(alias = "idProduct")]
+        pub fn product_id at 10 -> u16;
+
+        /// `bcdDevice` descriptor field: Device version.
+        #[doc(alias = "bcdDevice")]
+        pub fn device_version at 12 -> u16;
+
+        fn manufacturer_string_index_raw at 14 -> u8;
+        fn product_string_index_raw at 15 -> u8;
+        fn serial_number_string_index_raw at 16 -> u8;
+
+        /// `bNumConfigurations` descriptor field: Number of configurations.
+        #[doc(alias = "bNumConfigurations")]
+        pub fn num_configurations at 17 -> u8;
+    }
+}
+
+impl<'a> DeviceDescriptor<'a> {
+    /// Index of the string descriptor describing the manufacturer.
+    #[doc(alias = "iManufacturer")]
+    pub fn manufacturer_string_index(&self) -> Option<u8> {
+        Some(self.manufacturer_string_index_raw()).filter(|&i| i != 0)
+    }
+
+    /// Index of the string descriptor describing the product.
+    #[doc(alias = "iProduct")]
+    pub fn product_string_index(&self) -> Option<u8> {
+        Some(self.product_string_index_raw()).filter(|&i| i != 0)
+    }
+
+    /// Index of the string descriptor containing the device's serial number.
+    #[doc(alias = "iSerialNumber")]
+    pub fn serial_number_string_index(&self) -> Option<u8> {
+        Some(self.serial_number_string_index_raw()).filter(|&i| i != 0)
+    }
+}
+
+impl<'a> Debug for DeviceDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceDescriptor")
+            .field("usb_version", &format_args!("0x{:04X}", self.usb_version()))
+            .field("class", &format_args!("0x{:02X}", self.class()))
+            .field("subclass", &format_args!("0x{:02X}", self.subclass()))
+            .field("protocol", &format_args!("0x{:02X}", self.protocol()))
+            .field("max_packet_size_0", &self.max_packet_size_0())
+            .field("vendor_id", &format_args!("0x{:04X}", self.vendor_id()))
+            .field("product_id", &format_args!("0x{:04X}", self.product_id()))
+            .field(
+                "device_version",
+                &format_args!("0x{:04X}", self.device_version()),
+            )
+            .field(
+                "manufacturer_string_index",
+                &self.manufacturer_string_index(),
+            )
+            .field("product_string_index", &self.product_string_index())
+            .field(
+                "serial_number_string_index",
+                &self.serial_number_string_index(),
+            )
+            .field("num_configurations", &self.num_configurations())
+            .finish()
+    }
+}
+
 /// Information about a USB configuration with access to all associated interfaces, endpoints, and other descriptors.
 #[derive(Clone)]
 pub struct Configuration<'a>(&'a [u8]);
@@ -269,6 +412,63 @@ impl<'a> Configuration<'a> {
                 interfaces,
             })
     }
+
+    /// Iterate the interface association descriptors of this configuration.
+    pub fn interface_associations(&self) -> impl Iterator<Item = InterfaceAssociation<'a>> {
+        self.descriptors()
+            .filter(|d| {
+                d.descriptor_type() == DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION
+                    && d.len() >= DESCRIPTOR_LEN_INTERFACE_ASSOCIATION as usize
+            })
+            .map(|d| InterfaceAssociation(d.0))
+    }
+
+    /// Iterate the functions of this configuration, grouping together
+    /// interfaces that are associated by an interface association descriptor.
+    ///
+    /// Each interface not covered by an interface association descriptor is
+    /// returned as a function of its own. Functions are ordered by their
+    /// first interface number.
+    pub fn functions(&self) -> impl Iterator<Item = Function<'a>> {
+        let mut groups: BTreeMap<u8, InterfaceGroup<'a>> = self
+            .interfaces()
+            .map(|g| (g.interface_number(), g))
+            .collect();
+
+        let mut functions = Vec::new();
+
+        for association in self.interface_associations() {
+            let range = association.interface_range();
+            let numbers: Vec<u8> = groups
+                .keys()
+                .copied()
+                .filter(|&n| range.contains(&(n as u16)))
+                .collect();
+
+            functions.push(Function {
+                association: Some(association),
+                interfaces: numbers
+                    .into_iter()
+                    .filter_map(|n| groups.remove(&n))
+                    .collect(),
+            });
+        }
+
+        functions.extend(groups.into_values().map(|g| Function {
+            association: None,
+            interfaces: vec![g],
+        }));
+
+        functions.sort_by_key(|f| f.first_interface());
+        functions.into_iter()
+    }
+
+    /// Check the descriptors for violations of the USB specification.
+    ///
+    /// See [`validate_configuration`].
+    pub fn validate(&self, speed: Option<Speed>) -> Vec<DescriptorProblem> {
+        validate_configuration(self.0, speed)
+    }
 }
 
 descriptor_fields! {
@@ -363,6 +563,158 @@ impl<'a> InterfaceGroup<'a> {
     }
 }
 
+/// Information about a USB interface association descriptor, which groups
+/// the interfaces of a function of a composite device.
+#[derive(Clone)]
+pub struct InterfaceAssociation<'a>(&'a [u8]);
+
+impl<'a> InterfaceAssociation<'a> {
+    /// Range of interface numbers covered by this association, widened to
+    /// `u16` so that it can't overflow.
+    fn interface_range(&self) -> Range<u16> {
+        let first = self.first_interface() as u16;
+        first..first + self.interface_count() as u16
+    }
+}
+
+descriptor_fields! {
+    impl<'a> InterfaceAssociation<'a> {
+        /// `bFirstInterface` descriptor field: Number of the first interface of the function.
+        #[doc(alias = "bFirstInterface")]
+        pub fn first_interface at 2 -> u8;
+
+        /// `bInterfaceCount` descriptor field: Number of contiguous interfaces in the function.
+        #[doc(alias = "bInterfaceCount")]
+        pub fn interface_count at 3 -> u8;
+
+        /// `bFunctionClass` descriptor field: Standard class of the function.
+        #[doc(alias = "bFunctionClass")]
+        pub fn class at 4 -> u8;
+
+        /// `bFunctionSubClass` descriptor field: Standard subclass of the function.
+        #[doc(alias = "bFunctionSubClass")]
+        pub fn subclass at 5 -> u8;
+
+        /// `bFunctionProtocol` descriptor field: Standard protocol of the function.
+        #[doc(alias = "bFunctionProtocol")]
+        pub fn protocol at 6 -> u8;
+
+        fn string_index_raw at 7 -> u8;
+    }
+}
+
+impl<'a> InterfaceAssociation<'a> {
+    /// Index of the string descriptor describing this function.
+    #[doc(alias = "iFunction")]
+    pub fn string_index(&self) -> Option<u8> {
+        Some(self.string_index_raw()).filter(|&i| i != 0)
+    }
+}
+
+impl<'a> Debug for InterfaceAssociation<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterfaceAssociation")
+            .field("first_interface", &self.first_interface())
+            .field("interface_count", &self.interface_count())
+            .field("class", &self.class())
+            .field("subclass", &self.subclass())
+            .field("protocol", &self.protocol())
+            .field("string_index", &self.string_index())
+            .finish()
+    }
+}
+
+/// A function of a device: either the interfaces grouped by an
+/// [`InterfaceAssociation`], or a single interface that is not part of one.
+///
+/// Found with [`Configuration::functions`]. A driver for a composite device
+/// can use this to find a function by its class and claim all of its
+/// interfaces.
+#[derive(Clone)]
+pub struct Function<'a> {
+    association: Option<InterfaceAssociation<'a>>,
+    interfaces: Vec<InterfaceGroup<'a>>,
+}
+
+impl<'a> Function<'a> {
+    /// The interface association descriptor for this function, or `None` if
+    /// it is a single interface without one.
+    pub fn association(&self) -> Option<InterfaceAssociation<'a>> {
+        self.association.clone()
+    }
+
+    /// Iterator over the interfaces of the function.
+    pub fn interfaces(&self) -> impl Iterator<Item = InterfaceGroup<'a>> + '_ {
+        self.interfaces.iter().cloned()
+    }
+
+    /// Iterator over the interface numbers of the function.
+    pub fn interface_numbers(&self) -> impl Iterator<Item = u8> + '_ {
+        self.interfaces.iter().map(|i| i.interface_number())
+    }
+
+    /// Number of the first interface of the function.
+    pub fn first_interface(&self) -> u8 {
+        match &self.association {
+            Some(a) => a.first_interface(),
+            None => self.interfaces[0].interface_number(),
+        }
+    }
+
+    /// Class of the function, from the interface association descriptor or
+    /// the first alternate setting of the interface.
+    pub fn class(&self) -> u8 {
+        match &self.association {
+            Some(a) => a.class(),
+            None => self.interfaces[0].first_alt_setting().class(),
+        }
+    }
+
+    /// Subclass of the function, from the interface association descriptor or
+    /// the first alternate setting of the interface.
+    pub fn subclass(&self) -> u8 {
+        match &self.association {
+            Some(a) => a.subclass(),
+            None => self.interfaces[0].first_alt_setting().subclass(),
+        }
+    }
+
+    /// Protocol of the function, from the interface association descriptor or
+    /// the first alternate setting of the interface.
+    pub fn protocol(&self) -> u8 {
+        match &self.association {
+            Some(a) => a.protocol(),
+            None => self.interfaces[0].first_alt_setting().protocol(),
+        }
+    }
+
+    /// Index of the string descriptor describing the function, from the
+    /// interface association descriptor or the first alternate setting of
+    /// the interface.
+    pub fn string_index(&self) -> Option<u8> {
+        match &self.association {
+            Some(a) => a.string_index(),
+            None => self.interfaces[0].first_alt_setting().string_index(),
+        }
+    }
+}
+
+impl<'a> Debug for Function<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Function")
+            .field("first_interface", &self.first_interface())
+            .field("class", &self.class())
+            .field("subclass", &self.subclass())
+            .field("protocol", &self.protocol())
+            .field("string_index", &self.string_index())
+            .field(
+                "interface_numbers",
+                &DebugEntries(|| self.interface_numbers()),
+            )
+            .finish()
+    }
+}
+
 /// Information about a USB interface alternate setting, with access to associated endpoints and other descriptors.
 ///
 /// An interface descriptor represents a single alternate setting of
@@ -940,3 +1292,61 @@ fn test_dell_webcam() {
     assert!(alts.next().is_none());
     assert!(interfaces.next().is_none());
 }
+
+#[test]
+fn test_functions() {
+    let buf = ConfigurationBuilder::new(1)
+        .interface(InterfaceBuilder::new(0, 0).class(0x03).string_index(4))
+        .association(
+            InterfaceAssociationBuilder::new(1, 2)
+                .class(0x02)
+                .subclass(0x02)
+                .protocol(0x01)
+                .string_index(5),
+        )
+        .interface(InterfaceBuilder::new(1, 0).class(0x02).subclass(0x02))
+        .interface(InterfaceBuilder::new(2, 0).class(0x0a))
+        .interface(InterfaceBuilder::new(2, 1).class(0x0a))
+        .interface(InterfaceBuilder::new(3, 0).class(0xff))
+        .build();
+    let c = Configuration::new(&buf);
+
+    let associations: Vec<_> = c.interface_associations().collect();
+    assert_eq!(associations.len(), 1);
+    assert_eq!(associations[0].first_interface(), 1);
+    assert_eq!(associations[0].interface_count(), 2);
+
+    let functions: Vec<_> = c.functions().collect();
+    assert_eq!(functions.len(), 3);
+
+    assert!(functions[0].association().is_none());
+    assert_eq!(functions[0].interface_numbers().collect::<Vec<_>>(), [0]);
+    assert_eq!(functions[0].class(), 0x03);
+    assert_eq!(functions[0].string_index(), Some(4));
+
+    assert!(functions[1].association().is_some());
+    assert_eq!(functions[1].first_interface(), 1);
+    assert_eq!(functions[1].interface_numbers().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(
+        (
+            functions[1].class(),
+            functions[1].subclass(),
+            functions[1].protocol()
+        ),
+        (0x02, 0x02, 0x01)
+    );
+    assert_eq!(functions[1].string_index(), Some(5));
+    assert_eq!(
+        functions[1]
+            .interfaces()
+            .nth(1)
+            .unwrap()
+            .alt_settings()
+            .count(),
+        2
+    );
+
+    assert_eq!(functions[2].interface_numbers().collect::<Vec<_>>(), [3]);
+    assert_eq!(functions[2].class(), 0xff);
+    assert_eq!(functions[2].string_index(), None);
+}
@@ -0,0 +1,711 @@
+//! Builders that serialize descriptors to bytes.
+
+use std::collections::BTreeSet;
+
+use super::{
+    DESCRIPTOR_LEN_CONFIGURATION, DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_LEN_ENDPOINT,
+    DESCRIPTOR_LEN_INTERFACE, DESCRIPTOR_LEN_INTERFACE_ASSOCIATION, DESCRIPTOR_TYPE_CONFIGURATION,
+    DESCRIPTOR_TYPE_DEVICE, DESCRIPTOR_TYPE_ENDPOINT, DESCRIPTOR_TYPE_INTERFACE,
+    DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION, DESCRIPTOR_TYPE_STRING,
+};
+use crate::transfer::EndpointType;
+
+const DESCRIPTOR_TYPE_BOS: u8 = 0x0F;
+const DESCRIPTOR_TYPE_DEVICE_CAPABILITY: u8 = 0x10;
+const DESCRIPTOR_TYPE_SS_ENDPOINT_COMPANION: u8 = 0x30;
+
+const CAPABILITY_TYPE_USB2_EXTENSION: u8 = 0x02;
+const CAPABILITY_TYPE_SUPERSPEED_USB: u8 = 0x03;
+
+fn set_u16(buf: &mut [u8], pos: usize, value: u16) {
+    buf[pos..pos + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Check that `descriptor` is a single descriptor whose `bLength` matches its length.
+fn check_descriptor(descriptor: &[u8]) {
+    assert!(
+        descriptor.len() >= 2 && descriptor[0] as usize == descriptor.len(),
+        "descriptor bLength does not match its length"
+    );
+}
+
+/// Builder for a device descriptor.
+///
+/// Defaults to USB 2.0 with a 64 byte control endpoint and one
+/// configuration, and all other fields zero.
+///
+/// ```
+/// use nusb::descriptors::{DeviceDescriptor, DeviceDescriptorBuilder};
+/// let buf = DeviceDescriptorBuilder::new()
+///     .vendor_id(0x1209)
+///     .product_id(0x0001)
+///     .build();
+/// assert_eq!(DeviceDescriptor::new(&buf).vendor_id(), 0x1209);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DeviceDescriptorBuilder([u8; DESCRIPTOR_LEN_DEVICE as usize]);
+
+impl DeviceDescriptorBuilder {
+    /// Create a device descriptor builder with default values.
+    pub fn new() -> DeviceDescriptorBuilder {
+        let mut buf = [0; DESCRIPTOR_LEN_DEVICE as usize];
+        buf[0] = DESCRIPTOR_LEN_DEVICE;
+        buf[1] = DESCRIPTOR_TYPE_DEVICE;
+        set_u16(&mut buf, 2, 0x0200);
+        buf[7] = 64;
+        buf[17] = 1;
+        DeviceDescriptorBuilder(buf)
+    }
+
+    /// Set the `bcdUSB` field.
+    pub fn usb_version(mut self, usb_version: u16) -> Self {
+        set_u16(&mut self.0, 2, usb_version);
+        self
+    }
+
+    /// Set the `bDeviceClass` field.
+    pub fn class(mut self, class: u8) -> Self {
+        self.0[4] = class;
+        self
+    }
+
+    /// Set the `bDeviceSubClass` field.
+    pub fn subclass(mut self, subclass: u8) -> Self {
+        self.0[5] = subclass;
+        self
+    }
+
+    /// Set the `bDeviceProtocol` field.
+    pub fn protocol(mut self, protocol: u8) -> Self {
+        self.0[6] = protocol;
+        self
+    }
+
+    /// Set the `bMaxPacketSize0` field.
+    pub fn max_packet_size_0(mut self, max_packet_size_0: u8) -> Self {
+        self.0[7] = max_packet_size_0;
+        self
+    }
+
+    /// Set the `idVendor` field.
+    pub fn vendor_id(mut self, vendor_id: u16) -> Self {
+        set_u16(&mut self.0, 8, vendor_id);
+        self
+    }
+
+    /// Set the `idProduct` field.
+    pub fn product_id(mut self, product_id: u16) -> Self {
+        set_u16(&mut self.0, 10, product_id);
+        self
+    }
+
+    /// Set the `bcdDevice` field.
+    pub fn device_version(mut self, device_version: u16) -> Self {
+        set_u16(&mut self.0, 12, device_version);
+        self
+    }
+
+    /// Set the `iManufacturer` field.
+    pub fn manufacturer_string_index(mut self, index: u8) -> Self {
+        self.0[14] = index;
+        self
+    }
+
+    /// Set the `iProduct` field.
+    pub fn product_string_index(mut self, index: u8) -> Self {
+        self.0[15] = index;
+        self
+    }
+
+    /// Set the `iSerialNumber` field.
+    pub fn serial_number_string_index(mut self, index: u8) -> Self {
+        self.0[16] = index;
+        self
+    }
+
+    /// Set the `bNumConfigurations` field.
+    pub fn num_configurations(mut self, num_configurations: u8) -> Self {
+        self.0[17] = num_configurations;
+        self
+    }
+
+    /// Serialize the descriptor.
+    pub fn build(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+}
+
+impl Default for DeviceDescriptorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builder for a configuration descriptor followed by its interface,
+/// endpoint, and other descriptors.
+///
+/// Descriptors are serialized in the order they are added. The
+/// `wTotalLength` and `bNumInterfaces` fields are computed when the
+/// configuration is built.
+///
+/// ```
+/// use nusb::descriptors::{
+///     Configuration, ConfigurationBuilder, EndpointBuilder, InterfaceBuilder,
+/// };
+/// use nusb::transfer::EndpointType;
+///
+/// let buf = ConfigurationBuilder::new(1)
+///     .interface(
+///         InterfaceBuilder::new(0, 0)
+///             .class(0xff)
+///             .endpoint(EndpointBuilder::new(0x81, EndpointType::Bulk).max_packet_size(512))
+///             .endpoint(EndpointBuilder::new(0x02, EndpointType::Bulk).max_packet_size(512)),
+///     )
+///     .build();
+///
+/// let config = Configuration::new(&buf);
+/// assert_eq!(config.num_interfaces(), 1);
+/// assert_eq!(config.interface_alt_settings().next().unwrap().num_endpoints(), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ConfigurationBuilder {
+    header: [u8; DESCRIPTOR_LEN_CONFIGURATION as usize],
+    body: Vec<u8>,
+    interfaces: BTreeSet<u8>,
+}
+
+impl ConfigurationBuilder {
+    /// Create a configuration with the specified `bConfigurationValue`.
+    ///
+    /// It defaults to bus-powered with a maximum power of 100mA.
+    pub fn new(configuration_value: u8) -> ConfigurationBuilder {
+        let mut header = [0; DESCRIPTOR_LEN_CONFIGURATION as usize];
+        header[0] = DESCRIPTOR_LEN_CONFIGURATION;
+        header[1] = DESCRIPTOR_TYPE_CONFIGURATION;
+        header[5] = configuration_value;
+        header[7] = 0x80;
+        header[8] = 50;
+        ConfigurationBuilder {
+            header,
+            body: Vec::new(),
+            interfaces: BTreeSet::new(),
+        }
+    }
+
+    /// Set the `iConfiguration` field.
+    pub fn string_index(mut self, index: u8) -> Self {
+        self.header[6] = index;
+        self
+    }
+
+    /// Set the `bmAttributes` field.
+    ///
+    /// Bit 7 is reserved and always set.
+    pub fn attributes(mut self, attributes: u8) -> Self {
+        self.header[7] = attributes | 0x80;
+        self
+    }
+
+    /// Set the `bMaxPower` field, in units of **2** milliamps.
+    pub fn max_power(mut self, max_power: u8) -> Self {
+        self.header[8] = max_power;
+        self
+    }
+
+    /// Add an interface alternate setting and its endpoints.
+    pub fn interface(mut self, interface: InterfaceBuilder) -> Self {
+        self.interfaces.insert(interface.header[2]);
+        self.body.extend_from_slice(&interface.build());
+        self
+    }
+
+    /// Add an interface association descriptor, which should be followed
+    /// by the interfaces it groups.
+    pub fn association(mut self, association: InterfaceAssociationBuilder) -> Self {
+        self.body.extend_from_slice(&association.build());
+        self
+    }
+
+    /// Add another descriptor, including its `bLength` and
+    /// `bDescriptorType` fields.
+    ///
+    /// ### Panics
+    ///  * when the `bLength` field doesn't match the length of `descriptor`
+    pub fn descriptor(mut self, descriptor: &[u8]) -> Self {
+        check_descriptor(descriptor);
+        self.body.extend_from_slice(descriptor);
+        self
+    }
+
+    /// Serialize the configuration.
+    ///
+    /// ### Panics
+    ///  * when the total length is longer than 65535 bytes
+    pub fn build(&self) -> Vec<u8> {
+        let total_len = u16::try_from(self.header.len() + self.body.len())
+            .expect("configuration descriptors too long");
+        let mut header = self.header;
+        set_u16(&mut header, 2, total_len);
+        header[4] = self.interfaces.len() as u8;
+
+        let mut buf = header.to_vec();
+        buf.extend_from_slice(&self.body);
+        buf
+    }
+}
+
+/// Builder for an interface descriptor followed by its endpoint and other
+/// descriptors.
+///
+/// Descriptors are serialized in the order they are added. The
+/// `bNumEndpoints` field is computed when the interface is built.
+#[derive(Clone, Debug)]
+pub struct InterfaceBuilder {
+    header: [u8; DESCRIPTOR_LEN_INTERFACE as usize],
+    body: Vec<u8>,
+    num_endpoints: u8,
+}
+
+impl InterfaceBuilder {
+    /// Create an interface descriptor for the specified `bInterfaceNumber`
+    /// and `bAlternateSetting`.
+    pub fn new(interface_number: u8, alternate_setting: u8) -> InterfaceBuilder {
+        let mut header = [0; DESCRIPTOR_LEN_INTERFACE as usize];
+        header[0] = DESCRIPTOR_LEN_INTERFACE;
+        header[1] = DESCRIPTOR_TYPE_INTERFACE;
+        header[2] = interface_number;
+        header[3] = alternate_setting;
+        InterfaceBuilder {
+            header,
+            body: Vec::new(),
+            num_endpoints: 0,
+        }
+    }
+
+    /// Set the `bInterfaceClass` field.
+    pub fn class(mut self, class: u8) -> Self {
+        self.header[5] = class;
+        self
+    }
+
+    /// Set the `bInterfaceSubClass` field.
+    pub fn subclass(mut self, subclass: u8) -> Self {
+        self.header[6] = subclass;
+        self
+    }
+
+    /// Set the `bInterfaceProtocol` field.
+    pub fn protocol(mut self, protocol: u8) -> Self {
+        self.header[7] = protocol;
+        self
+    }
+
+    /// Set the `iInterface` field.
+    pub fn string_index(mut self, index: u8) -> Self {
+        self.header[8] = index;
+        self
+    }
+
+    /// Add an endpoint.
+    pub fn endpoint(mut self, endpoint: EndpointBuilder) -> Self {
+        self.num_endpoints += 1;
+        self.body.extend_from_slice(&endpoint.build());
+        self
+    }
+
+    /// Add another descriptor, such as a class-specific descriptor,
+    /// including its `bLength` and `bDescriptorType` fields.
+    ///
+    /// ### Panics
+    ///  * when the `bLength` field doesn't match the length of `descriptor`
+    pub fn descriptor(mut self, descriptor: &[u8]) -> Self {
+        check_descriptor(descriptor);
+        self.body.extend_from_slice(descriptor);
+        self
+    }
+
+    /// Serialize the interface descriptor and the descriptors that follow it.
+    pub fn build(&self) -> Vec<u8> {
+        let mut header = self.header;
+        header[4] = self.num_endpoints;
+
+        let mut buf = header.to_vec();
+        buf.extend_from_slice(&self.body);
+        buf
+    }
+}
+
+/// Builder for an interface association descriptor, which groups the
+/// interfaces of a function of a composite device.
+#[derive(Clone, Debug)]
+pub struct InterfaceAssociationBuilder([u8; DESCRIPTOR_LEN_INTERFACE_ASSOCIATION as usize]);
+
+impl InterfaceAssociationBuilder {
+    /// Create an interface association for `interface_count` interfaces
+    /// starting with `first_interface`.
+    pub fn new(first_interface: u8, interface_count: u8) -> InterfaceAssociationBuilder {
+        InterfaceAssociationBuilder([
+            DESCRIPTOR_LEN_INTERFACE_ASSOCIATION,
+            DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION,
+            first_interface,
+            interface_count,
+            0,
+            0,
+            0,
+            0,
+        ])
+    }
+
+    /// Set the `bFunctionClass` field.
+    pub fn class(mut self, class: u8) -> Self {
+        self.0[4] = class;
+        self
+    }
+
+    /// Set the `bFunctionSubClass` field.
+    pub fn subclass(mut self, subclass: u8) -> Self {
+        self.0[5] = subclass;
+        self
+    }
+
+    /// Set the `bFunctionProtocol` field.
+    pub fn protocol(mut self, protocol: u8) -> Self {
+        self.0[6] = protocol;
+        self
+    }
+
+    /// Set the `iFunction` field.
+    pub fn string_index(mut self, index: u8) -> Self {
+        self.0[7] = index;
+        self
+    }
+
+    /// Serialize the descriptor.
+    pub fn build(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+}
+
+/// Builder for an endpoint descriptor, followed by its SuperSpeed companion
+/// and other descriptors.
+#[derive(Clone, Debug)]
+pub struct EndpointBuilder {
+    header: [u8; DESCRIPTOR_LEN_ENDPOINT as usize],
+    body: Vec<u8>,
+}
+
+impl EndpointBuilder {
+    /// Create an endpoint descriptor with the specified `bEndpointAddress`
+    /// and transfer type.
+    pub fn new(address: u8, transfer_type: EndpointType) -> EndpointBuilder {
+        let mut header = [0; DESCRIPTOR_LEN_ENDPOINT as usize];
+        header[0] = DESCRIPTOR_LEN_ENDPOINT;
+        header[1] = DESCRIPTOR_TYPE_ENDPOINT;
+        header[2] = address;
+        header[3] = transfer_type as u8;
+        EndpointBuilder {
+            header,
+            body: Vec::new(),
+        }
+    }
+
+    /// Set the `bmAttributes` field, including the transfer type.
+    pub fn attributes(mut self, attributes: u8) -> Self {
+        self.header[3] = attributes;
+        self
+    }
+
+    /// Set the `wMaxPacketSize` field.
+    ///
+    /// For high-bandwidth isochronous and interrupt endpoints, bits 11-12
+    /// are the number of additional transactions per microframe.
+    pub fn max_packet_size(mut self, max_packet_size: u16) -> Self {
+        set_u16(&mut self.header, 4, max_packet_size);
+        self
+    }
+
+    /// Set the `bInterval` field.
+    pub fn interval(mut self, interval: u8) -> Self {
+        self.header[6] = interval;
+        self
+    }
+
+    /// Add a SuperSpeed endpoint companion descriptor.
+    pub fn superspeed_companion(mut self, companion: SuperSpeedCompanionBuilder) -> Self {
+        self.body.extend_from_slice(&companion.build());
+        self
+    }
+
+    /// Add another descriptor, such as a class-specific descriptor,
+    /// including its `bLength` and `bDescriptorType` fields.
+    ///
+    /// ### Panics
+    ///  * when the `bLength` field doesn't match the length of `descriptor`
+    pub fn descriptor(mut self, descriptor: &[u8]) -> Self {
+        check_descriptor(descriptor);
+        self.body.extend_from_slice(descriptor);
+        self
+    }
+
+    /// Serialize the endpoint descriptor and the descriptors that follow it.
+    pub fn build(&self) -> Vec<u8> {
+        let mut buf = self.header.to_vec();
+        buf.extend_from_slice(&self.body);
+        buf
+    }
+}
+
+/// Builder for a SuperSpeed endpoint companion descriptor.
+#[derive(Clone, Debug, Default)]
+pub struct SuperSpeedCompanionBuilder {
+    max_burst: u8,
+    attributes: u8,
+    bytes_per_interval: u16,
+}
+
+impl SuperSpeedCompanionBuilder {
+    /// Create a companion descriptor with all fields zero.
+    pub fn new() -> SuperSpeedCompanionBuilder {
+        SuperSpeedCompanionBuilder::default()
+    }
+
+    /// Set the `bMaxBurst` field: the number of additional packets the
+    /// endpoint can send or receive in a burst.
+    pub fn max_burst(mut self, max_burst: u8) -> Self {
+        self.max_burst = max_burst;
+        self
+    }
+
+    /// Set the `bmAttributes` field: `MaxStreams` for bulk endpoints, or
+    /// `Mult` for isochronous endpoints.
+    pub fn attributes(mut self, attributes: u8) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Set the `wBytesPerInterval` field for periodic endpoints.
+    pub fn bytes_per_interval(mut self, bytes_per_interval: u16) -> Self {
+        self.bytes_per_interval = bytes_per_interval;
+        self
+    }
+
+    /// Serialize the descriptor.
+    pub fn build(&self) -> Vec<u8> {
+        let [b0, b1] = self.bytes_per_interval.to_le_bytes();
+        vec![
+            6,
+            DESCRIPTOR_TYPE_SS_ENDPOINT_COMPANION,
+            self.max_burst,
+            self.attributes,
+            b0,
+            b1,
+        ]
+    }
+}
+
+/// Serialize a string descriptor containing `s`.
+///
+/// ### Panics
+///  * when `s` is longer than 126 UTF-16 code units
+pub fn build_string_descriptor(s: &str) -> Vec<u8> {
+    let mut buf = vec![0, DESCRIPTOR_TYPE_STRING];
+    buf.extend(s.encode_utf16().flat_map(|c| c.to_le_bytes()));
+    buf[0] = u8::try_from(buf.len()).expect("string too long for a string descriptor");
+    buf
+}
+
+/// Serialize string descriptor zero, listing the supported
+/// [language IDs][super::language_id].
+///
+/// ### Panics
+///  * when more than 126 language IDs are specified
+pub fn build_language_ids_descriptor(language_ids: &[u16]) -> Vec<u8> {
+    let mut buf = vec![0, DESCRIPTOR_TYPE_STRING];
+    buf.extend(language_ids.iter().flat_map(|c| c.to_le_bytes()));
+    buf[0] = u8::try_from(buf.len()).expect("too many language IDs");
+    buf
+}
+
+/// Builder for a Binary Object Store (BOS) descriptor followed by its device
+/// capability descriptors.
+///
+/// The `wTotalLength` and `bNumDeviceCaps` fields are computed when the
+/// descriptor is built.
+#[derive(Clone, Debug, Default)]
+pub struct BosBuilder {
+    body: Vec<u8>,
+    num_capabilities: u8,
+}
+
+impl BosBuilder {
+    /// Create a BOS descriptor with no capabilities.
+    pub fn new() -> BosBuilder {
+        BosBuilder::default()
+    }
+
+    /// Add a device capability descriptor with the specified
+    /// `bDevCapabilityType` and the fields that follow it.
+    ///
+    /// ### Panics
+    ///  * when `data` is longer than 252 bytes
+    pub fn capability(mut self, capability_type: u8, data: &[u8]) -> Self {
+        let len = u8::try_from(data.len() + 3).expect("device capability too long");
+        self.body
+            .extend_from_slice(&[len, DESCRIPTOR_TYPE_DEVICE_CAPABILITY, capability_type]);
+        self.body.extend_from_slice(data);
+        self.num_capabilities += 1;
+        self
+    }
+
+    /// Add a USB 2.0 extension capability with the specified `bmAttributes`,
+    /// e.g. `0x02` for Link Power Management support.
+    pub fn usb2_extension(self, attributes: u32) -> Self {
+        self.capability(CAPABILITY_TYPE_USB2_EXTENSION, &attributes.to_le_bytes())
+    }
+
+    /// Add a SuperSpeed USB capability.
+    ///
+    /// `speeds_supported` is a bitmap of the supported speeds, with bit 0 for
+    /// low speed through bit 3 for SuperSpeed, and `functionality_support` is
+    /// the lowest speed at which all functionality is available. The exit
+    /// latencies are set to their maximum values.
+    pub fn superspeed_usb(self, speeds_supported: u16, functionality_support: u8) -> Self {
+        let [s0, s1] = speeds_supported.to_le_bytes();
+        self.capability(
+            CAPABILITY_TYPE_SUPERSPEED_USB,
+            &[0, s0, s1, functionality_support, 0x0A, 0xFF, 0x07],
+        )
+    }
+
+    /// Serialize the BOS descriptor and its device capabilities.
+    ///
+    /// ### Panics
+    ///  * when the total length is longer than 65535 bytes
+    pub fn build(&self) -> Vec<u8> {
+        let total_len = u16::try_from(5 + self.body.len()).expect("BOS descriptors too long");
+        let [t0, t1] = total_len.to_le_bytes();
+        let mut buf = vec![5, DESCRIPTOR_TYPE_BOS, t0, t1, self.num_capabilities];
+        buf.extend_from_slice(&self.body);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::descriptors::{
+        decode_string_descriptor, Configuration, Descriptors, DeviceDescriptor,
+    };
+
+    #[test]
+    fn test_device() {
+        let buf = DeviceDescriptorBuilder::new()
+            .usb_version(0x0210)
+            .class(0xef)
+            .subclass(0x02)
+            .protocol(0x01)
+            .vendor_id(0x1209)
+            .product_id(0x0010)
+            .device_version(0x0100)
+            .serial_number_string_index(3)
+            .build();
+
+        let desc = DeviceDescriptor::new(&buf);
+        assert_eq!(desc.usb_version(), 0x0210);
+        assert_eq!(
+            (desc.class(), desc.subclass(), desc.protocol()),
+            (0xef, 2, 1)
+        );
+        assert_eq!((desc.vendor_id(), desc.product_id()), (0x1209, 0x0010));
+        assert_eq!(desc.max_packet_size_0(), 64);
+        assert_eq!(desc.manufacturer_string_index(), None);
+        assert_eq!(desc.serial_number_string_index(), Some(3));
+        assert_eq!(desc.num_configurations(), 1);
+    }
+
+    #[test]
+    fn test_configuration() {
+        let buf = ConfigurationBuilder::new(1)
+            .max_power(250)
+            .association(
+                InterfaceAssociationBuilder::new(0, 2)
+                    .class(0x02)
+                    .subclass(0x02),
+            )
+            .interface(
+                InterfaceBuilder::new(0, 0)
+                    .class(0x02)
+                    .subclass(0x02)
+                    .descriptor(&[5, 0x24, 0x00, 0x10, 0x01])
+                    .endpoint(
+                        EndpointBuilder::new(0x83, EndpointType::Interrupt)
+                            .max_packet_size(16)
+                            .interval(10),
+                    ),
+            )
+            .interface(InterfaceBuilder::new(1, 0).class(0x0a))
+            .interface(
+                InterfaceBuilder::new(1, 1)
+                    .class(0x0a)
+                    .endpoint(
+                        EndpointBuilder::new(0x81, EndpointType::Bulk)
+                            .max_packet_size(1024)
+                            .superspeed_companion(SuperSpeedCompanionBuilder::new().max_burst(15)),
+                    )
+                    .endpoint(EndpointBuilder::new(0x02, EndpointType::Bulk).max_packet_size(1024)),
+            )
+            .build();
+
+        let config = Configuration::new(&buf);
+        assert_eq!(config.configuration_value(), 1);
+        assert_eq!(config.num_interfaces(), 2);
+        assert_eq!(config.attributes(), 0x80);
+        assert_eq!(config.max_power(), 250);
+        assert_eq!(config.interfaces().count(), 2);
+
+        let alts: Vec<_> = config.interface_alt_settings().collect();
+        assert_eq!(alts.len(), 3);
+        assert_eq!(alts[0].num_endpoints(), 1);
+        assert_eq!(
+            alts[0].descriptors().nth(1).unwrap().descriptor_type(),
+            0x24
+        );
+        assert_eq!(alts[1].num_endpoints(), 0);
+        assert_eq!(alts[2].num_endpoints(), 2);
+
+        let ep = alts[2].endpoints().next().unwrap();
+        assert_eq!(ep.address(), 0x81);
+        assert_eq!(ep.transfer_type(), EndpointType::Bulk);
+        assert_eq!(ep.max_packet_size(), 1024);
+        let companion = ep.descriptors().nth(1).unwrap();
+        assert_eq!(&companion[..], &[6, 0x30, 15, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_string() {
+        let buf = build_string_descriptor("Composite Gadget ☃");
+        assert_eq!(
+            decode_string_descriptor(&buf).unwrap(),
+            "Composite Gadget ☃"
+        );
+
+        assert_eq!(build_language_ids_descriptor(&[0x0409]), [4, 3, 0x09, 0x04]);
+    }
+
+    #[test]
+    fn test_bos() {
+        let buf = BosBuilder::new()
+            .usb2_extension(0x02)
+            .superspeed_usb(0x0e, 1)
+            .build();
+
+        assert_eq!(buf.len(), 5 + 7 + 10);
+        assert_eq!(u16::from_le_bytes([buf[2], buf[3]]) as usize, buf.len());
+        assert_eq!(buf[4], 2);
+
+        let caps: Vec<_> = Descriptors(&buf[5..])
+            .map(|d| (d.descriptor_type(), d[2]))
+            .collect();
+        assert_eq!(caps, [(0x10, 0x02), (0x10, 0x03)]);
+    }
+}
@@ -0,0 +1,288 @@
+//! Reference-counted variants of the descriptor types.
+
+use std::{fmt::Debug, ops::Range, sync::Arc};
+
+use super::{
+    Configuration, ConfigurationSnapshot, Descriptor, Descriptors, Endpoint, Function,
+    InterfaceAltSetting, InterfaceAssociation, DESCRIPTOR_LEN_ENDPOINT, DESCRIPTOR_TYPE_ENDPOINT,
+};
+use crate::transfer::{Direction, EndpointType};
+
+/// Define methods that call the method of the same name on the borrowed type.
+macro_rules! delegate_fields {
+    ($tname:ident as $borrowed:ident via $as_borrowed:ident {
+        $( fn $name:ident -> $ty:ty; )*
+    }) => {
+        impl $tname {
+            $(
+                #[doc = concat!("See [`", stringify!($borrowed), "::", stringify!($name), "`].")]
+                #[inline]
+                pub fn $name(&self) -> $ty {
+                    self.$as_borrowed().$name()
+                }
+            )*
+        }
+    };
+}
+
+/// Find the range of `sub` within `buf`, which it must borrow from.
+fn subrange(buf: &[u8], sub: &[u8]) -> Range<usize> {
+    let start = sub.as_ptr() as usize - buf.as_ptr() as usize;
+    debug_assert!(start + sub.len() <= buf.len());
+    start..start + sub.len()
+}
+
+/// A [`Configuration`] that owns its descriptors.
+///
+/// The descriptors are reference-counted, so this is cheap to clone, and the
+/// interfaces and endpoints returned from it share the same buffer. Unlike
+/// `Configuration`, it can be stored in a struct or sent to another task
+/// without borrowing the [`Device`][crate::Device].
+///
+/// Found with
+/// [`Device::active_configuration_owned`][crate::Device::active_configuration_owned]
+/// and [`Device::configurations_owned`][crate::Device::configurations_owned],
+/// or created from a `Configuration` with `OwnedConfiguration::from`.
+///
+/// To compare or serialize the descriptors, convert it to a
+/// [`ConfigurationSnapshot`] with `ConfigurationSnapshot::from`.
+#[derive(Clone)]
+pub struct OwnedConfiguration(Arc<[u8]>);
+
+impl OwnedConfiguration {
+    /// Borrow as a [`Configuration`].
+    pub fn as_configuration(&self) -> Configuration<'_> {
+        Configuration(&self.0)
+    }
+
+    /// See [`Configuration::descriptors`].
+    pub fn descriptors(&self) -> Descriptors<'_> {
+        self.as_configuration().descriptors()
+    }
+
+    /// See [`Configuration::interface_alt_settings`].
+    pub fn interface_alt_settings(&self) -> impl Iterator<Item = OwnedInterfaceAltSetting> + '_ {
+        self.as_configuration()
+            .interface_alt_settings()
+            .map(|i| self.alt_setting(&i))
+    }
+
+    /// See [`Configuration::interfaces`].
+    pub fn interfaces(&self) -> impl Iterator<Item = OwnedInterfaceGroup> + '_ {
+        self.as_configuration()
+            .interfaces()
+            .map(|g| OwnedInterfaceGroup {
+                interface_number: g.interface_number(),
+                alt_settings: g.alt_settings().map(|i| self.alt_setting(&i)).collect(),
+            })
+    }
+
+    /// See [`Configuration::interface_associations`].
+    pub fn interface_associations(&self) -> impl Iterator<Item = InterfaceAssociation<'_>> {
+        self.as_configuration().interface_associations()
+    }
+
+    /// See [`Configuration::functions`].
+    pub fn functions(&self) -> impl Iterator<Item = Function<'_>> {
+        self.as_configuration().functions()
+    }
+
+    fn alt_setting(&self, i: &InterfaceAltSetting) -> OwnedInterfaceAltSetting {
+        OwnedInterfaceAltSetting {
+            range: subrange(&self.0, i.0),
+            buf: self.0.clone(),
+        }
+    }
+}
+
+delegate_fields! {
+    OwnedConfiguration as Configuration via as_configuration {
+        fn num_interfaces -> u8;
+        fn configuration_value -> u8;
+        fn attributes -> u8;
+        fn max_power -> u8;
+        fn string_index -> Option<u8>;
+    }
+}
+
+impl<'a> From<Configuration<'a>> for OwnedConfiguration {
+    fn from(c: Configuration<'a>) -> Self {
+        OwnedConfiguration(c.0.into())
+    }
+}
+
+impl From<&OwnedConfiguration> for ConfigurationSnapshot {
+    fn from(c: &OwnedConfiguration) -> Self {
+        ConfigurationSnapshot::from(&c.as_configuration())
+    }
+}
+
+impl Debug for OwnedConfiguration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.as_configuration(), f)
+    }
+}
+
+/// An [`InterfaceGroup`][super::InterfaceGroup] whose alternate settings
+/// share ownership of their configuration's descriptors.
+///
+/// See [`OwnedConfiguration`].
+#[derive(Clone, Debug)]
+pub struct OwnedInterfaceGroup {
+    interface_number: u8,
+    alt_settings: Vec<OwnedInterfaceAltSetting>,
+}
+
+impl OwnedInterfaceGroup {
+    /// See [`InterfaceGroup::interface_number`][super::InterfaceGroup::interface_number].
+    pub fn interface_number(&self) -> u8 {
+        self.interface_number
+    }
+
+    /// See [`InterfaceGroup::alt_settings`][super::InterfaceGroup::alt_settings].
+    pub fn alt_settings(&self) -> impl Iterator<Item = OwnedInterfaceAltSetting> + '_ {
+        self.alt_settings.iter().cloned()
+    }
+
+    /// See [`InterfaceGroup::first_alt_setting`][super::InterfaceGroup::first_alt_setting].
+    pub fn first_alt_setting(&self) -> OwnedInterfaceAltSetting {
+        self.alt_settings[0].clone()
+    }
+}
+
+/// An [`InterfaceAltSetting`] that shares ownership of its configuration's
+/// descriptors.
+///
+/// See [`OwnedConfiguration`].
+#[derive(Clone)]
+pub struct OwnedInterfaceAltSetting {
+    buf: Arc<[u8]>,
+    range: Range<usize>,
+}
+
+impl OwnedInterfaceAltSetting {
+    /// Borrow as an [`InterfaceAltSetting`].
+    pub fn as_interface_alt_setting(&self) -> InterfaceAltSetting<'_> {
+        InterfaceAltSetting(&self.buf[self.range.clone()])
+    }
+
+    /// See [`InterfaceAltSetting::descriptors`].
+    pub fn descriptors(&self) -> Descriptors<'_> {
+        Descriptors(&self.buf[self.range.clone()])
+    }
+
+    /// See [`InterfaceAltSetting::endpoints`].
+    pub fn endpoints(&self) -> impl Iterator<Item = OwnedEndpoint> + '_ {
+        self.descriptors()
+            .split_by_type(DESCRIPTOR_TYPE_ENDPOINT, DESCRIPTOR_LEN_ENDPOINT)
+            .map(|e| OwnedEndpoint {
+                range: subrange(&self.buf, e),
+                buf: self.buf.clone(),
+            })
+    }
+}
+
+delegate_fields! {
+    OwnedInterfaceAltSetting as InterfaceAltSetting via as_interface_alt_setting {
+        fn interface_number -> u8;
+        fn alternate_setting -> u8;
+        fn num_endpoints -> u8;
+        fn class -> u8;
+        fn subclass -> u8;
+        fn protocol -> u8;
+        fn string_index -> Option<u8>;
+    }
+}
+
+impl Debug for OwnedInterfaceAltSetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.as_interface_alt_setting(), f)
+    }
+}
+
+/// An [`Endpoint`] that shares ownership of its configuration's descriptors.
+///
+/// See [`OwnedConfiguration`].
+#[derive(Clone)]
+pub struct OwnedEndpoint {
+    buf: Arc<[u8]>,
+    range: Range<usize>,
+}
+
+impl OwnedEndpoint {
+    /// Borrow as an [`Endpoint`].
+    pub fn as_endpoint(&self) -> Endpoint<'_> {
+        Endpoint(&self.buf[self.range.clone()])
+    }
+
+    /// See [`Endpoint::descriptors`].
+    pub fn descriptors(&self) -> impl Iterator<Item = Descriptor<'_>> {
+        Descriptors(&self.buf[self.range.clone()])
+    }
+}
+
+delegate_fields! {
+    OwnedEndpoint as Endpoint via as_endpoint {
+        fn address -> u8;
+        fn attributes -> u8;
+        fn max_packet_size_raw -> u16;
+        fn interval -> u8;
+        fn direction -> Direction;
+        fn transfer_type -> EndpointType;
+        fn max_packet_size -> usize;
+        fn packets_per_microframe -> u8;
+    }
+}
+
+impl Debug for OwnedEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.as_endpoint(), f)
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_owned() {
+    let buf = [
+        0x09, 0x02, 0x28, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+        0x08, 0x0b, 0x00, 0x01, 0xff, 0x00, 0x00, 0x00,
+        0x09, 0x04, 0x00, 0x00, 0x02, 0xff, 0x00, 0x00, 0x00,
+        0x07, 0x05, 0x81, 0x02, 0x00, 0x02, 0x00,
+        0x07, 0x05, 0x02, 0x02, 0x00, 0x02, 0x00,
+    ];
+    let config = OwnedConfiguration::from(Configuration::new(&buf));
+    assert_eq!(config.configuration_value(), 1);
+    assert_eq!(config.max_power(), 0x32);
+    assert_eq!(config.interface_associations().count(), 1);
+    assert_eq!(config.functions().count(), 1);
+    assert!(config.functions().next().unwrap().association().is_some());
+    assert_eq!(
+        ConfigurationSnapshot::from(&config),
+        ConfigurationSnapshot::from(&Configuration::new(&buf))
+    );
+
+    let (group, interface, endpoints) = std::thread::spawn(move || {
+        let group = config.interfaces().next().unwrap();
+        let interface = config.interface_alt_settings().next().unwrap();
+        let endpoints: Vec<_> = interface.endpoints().collect();
+        (group, interface, endpoints)
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(group.interface_number(), 0);
+    assert_eq!(group.alt_settings().count(), 1);
+    assert_eq!(group.first_alt_setting().class(), 0xff);
+    assert_eq!(interface.class(), 0xff);
+    assert_eq!(interface.num_endpoints(), 2);
+    assert_eq!(endpoints.len(), 2);
+    assert_eq!(endpoints[1].address(), 0x02);
+    assert_eq!(endpoints[1].direction(), Direction::Out);
+    assert_eq!(endpoints[1].transfer_type(), EndpointType::Bulk);
+    assert_eq!(endpoints[1].max_packet_size(), 512);
+    assert_eq!(endpoints[1].descriptors().count(), 1);
+    assert_eq!(
+        format!("{:?}", endpoints[0]),
+        format!("{:?}", endpoints[0].as_endpoint())
+    );
+}
@@ -0,0 +1,204 @@
+//! Owned copies of the configuration descriptor tree.
+
+use super::{
+    Configuration, Endpoint, InterfaceAltSetting, DESCRIPTOR_TYPE_ENDPOINT,
+    DESCRIPTOR_TYPE_INTERFACE,
+};
+
+/// An owned copy of a [`Configuration`] and its interfaces and endpoints.
+///
+/// Unlike `Configuration`, which borrows from the descriptor buffer, a
+/// snapshot can be stored, compared with `==`, and with the `serde` feature
+/// enabled, serialized. Create one with `ConfigurationSnapshot::from`, from a
+/// `Configuration` or an [`OwnedConfiguration`][super::OwnedConfiguration].
+///
+/// ```
+/// # let config_buf = [9, 2, 9, 0, 0, 1, 0, 0x80, 50];
+/// use nusb::descriptors::{Configuration, ConfigurationSnapshot};
+/// let config = Configuration::new(&config_buf);
+/// let snapshot = ConfigurationSnapshot::from(&config);
+/// assert_eq!(snapshot.configuration_value, 1);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigurationSnapshot {
+    /// See [`Configuration::configuration_value`].
+    pub configuration_value: u8,
+
+    /// See [`Configuration::num_interfaces`].
+    pub num_interfaces: u8,
+
+    /// See [`Configuration::attributes`].
+    pub attributes: u8,
+
+    /// See [`Configuration::max_power`].
+    pub max_power: u8,
+
+    /// See [`Configuration::string_index`].
+    pub string_index: Option<u8>,
+
+    /// Descriptors following the configuration descriptor, before the first
+    /// interface.
+    pub other_descriptors: Vec<Vec<u8>>,
+
+    /// Each interface and alternate setting, in descriptor order.
+    pub interface_alt_settings: Vec<InterfaceAltSettingSnapshot>,
+}
+
+impl<'a> From<&Configuration<'a>> for ConfigurationSnapshot {
+    fn from(c: &Configuration<'a>) -> Self {
+        ConfigurationSnapshot {
+            configuration_value: c.configuration_value(),
+            num_interfaces: c.num_interfaces(),
+            attributes: c.attributes(),
+            max_power: c.max_power(),
+            string_index: c.string_index(),
+            other_descriptors: c
+                .descriptors()
+                .skip(1)
+                .take_while(|d| d.descriptor_type() != DESCRIPTOR_TYPE_INTERFACE)
+                .map(|d| d.to_vec())
+                .collect(),
+            interface_alt_settings: c
+                .interface_alt_settings()
+                .map(|i| InterfaceAltSettingSnapshot::from(&i))
+                .collect(),
+        }
+    }
+}
+
+/// An owned copy of an [`InterfaceAltSetting`] and its endpoints.
+///
+/// See [`ConfigurationSnapshot`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterfaceAltSettingSnapshot {
+    /// See [`InterfaceAltSetting::interface_number`].
+    pub interface_number: u8,
+
+    /// See [`InterfaceAltSetting::alternate_setting`].
+    pub alternate_setting: u8,
+
+    /// See [`InterfaceAltSetting::class`].
+    pub class: u8,
+
+    /// See [`InterfaceAltSetting::subclass`].
+    pub subclass: u8,
+
+    /// See [`InterfaceAltSetting::protocol`].
+    pub protocol: u8,
+
+    /// See [`InterfaceAltSetting::string_index`].
+    pub string_index: Option<u8>,
+
+    /// Descriptors following the interface descriptor, before the first
+    /// endpoint, such as class-specific descriptors.
+    pub other_descriptors: Vec<Vec<u8>>,
+
+    /// The interface's endpoints.
+    pub endpoints: Vec<EndpointSnapshot>,
+}
+
+impl<'a> From<&InterfaceAltSetting<'a>> for InterfaceAltSettingSnapshot {
+    fn from(i: &InterfaceAltSetting<'a>) -> Self {
+        InterfaceAltSettingSnapshot {
+            interface_number: i.interface_number(),
+            alternate_setting: i.alternate_setting(),
+            class: i.class(),
+            subclass: i.subclass(),
+            protocol: i.protocol(),
+            string_index: i.string_index(),
+            other_descriptors: i
+                .descriptors()
+                .skip(1)
+                .take_while(|d| d.descriptor_type() != DESCRIPTOR_TYPE_ENDPOINT)
+                .map(|d| d.to_vec())
+                .collect(),
+            endpoints: i.endpoints().map(|e| EndpointSnapshot::from(&e)).collect(),
+        }
+    }
+}
+
+/// An owned copy of an [`Endpoint`].
+///
+/// See [`ConfigurationSnapshot`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EndpointSnapshot {
+    /// See [`Endpoint::address`].
+    pub address: u8,
+
+    /// See [`Endpoint::attributes`].
+    pub attributes: u8,
+
+    /// See [`Endpoint::max_packet_size_raw`].
+    pub max_packet_size_raw: u16,
+
+    /// See [`Endpoint::interval`].
+    pub interval: u8,
+
+    /// Descriptors following the endpoint descriptor, such as SuperSpeed
+    /// endpoint companion descriptors.
+    pub other_descriptors: Vec<Vec<u8>>,
+}
+
+impl<'a> From<&Endpoint<'a>> for EndpointSnapshot {
+    fn from(e: &Endpoint<'a>) -> Self {
+        EndpointSnapshot {
+            address: e.address(),
+            attributes: e.attributes(),
+            max_packet_size_raw: e.max_packet_size_raw(),
+            interval: e.interval(),
+            other_descriptors: e.descriptors().skip(1).map(|d| d.to_vec()).collect(),
+        }
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_snapshot() {
+    let buf = [
+        0x09, 0x02, 0x1e, 0x00, 0x01, 0x01, 0x00, 0xe0, 0x00,
+        0x09, 0x04, 0x00, 0x00, 0x01, 0x09, 0x00, 0x00, 0x00,
+        0x07, 0x05, 0x81, 0x03, 0x04, 0x00, 0x0c,
+        0x05, 0x25, 0x03, 0x80, 0x00,
+    ];
+    let snapshot = ConfigurationSnapshot::from(&Configuration::new(&buf));
+
+    assert_eq!(
+        snapshot,
+        ConfigurationSnapshot {
+            configuration_value: 1,
+            num_interfaces: 1,
+            attributes: 0xe0,
+            max_power: 0,
+            string_index: None,
+            other_descriptors: vec![],
+            interface_alt_settings: vec![InterfaceAltSettingSnapshot {
+                interface_number: 0,
+                alternate_setting: 0,
+                class: 9,
+                subclass: 0,
+                protocol: 0,
+                string_index: None,
+                other_descriptors: vec![],
+                endpoints: vec![EndpointSnapshot {
+                    address: 0x81,
+                    attributes: 0x03,
+                    max_packet_size_raw: 4,
+                    interval: 12,
+                    other_descriptors: vec![vec![0x05, 0x25, 0x03, 0x80, 0x00]],
+                }],
+            }],
+        }
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ConfigurationSnapshot>(&json).unwrap(),
+            snapshot
+        );
+    }
+}
@@ -0,0 +1,638 @@
+//! Strict validation of configuration descriptors.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use super::{
+    DESCRIPTOR_LEN_CONFIGURATION, DESCRIPTOR_LEN_ENDPOINT, DESCRIPTOR_LEN_INTERFACE,
+    DESCRIPTOR_LEN_INTERFACE_ASSOCIATION, DESCRIPTOR_TYPE_CONFIGURATION, DESCRIPTOR_TYPE_ENDPOINT,
+    DESCRIPTOR_TYPE_INTERFACE, DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION,
+};
+use crate::{transfer::EndpointType, Speed};
+
+/// A problem found by [`validate_configuration`].
+///
+/// The parsers in this module tolerate most of these, either by skipping the
+/// offending descriptor or by ignoring the rest of the buffer, but a device
+/// that produces them is not compliant with the USB specification.
+///
+/// Offsets are in bytes from the start of the configuration descriptor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DescriptorProblem {
+    /// The buffer is too short to contain a configuration descriptor.
+    ///
+    /// No further validation is possible.
+    BufferTooShort {
+        /// Length of the buffer.
+        len: usize,
+    },
+
+    /// The first descriptor is not a configuration descriptor.
+    ///
+    /// No further validation is possible.
+    NotConfiguration {
+        /// `bDescriptorType` of the first descriptor.
+        descriptor_type: u8,
+    },
+
+    /// `wTotalLength` doesn't match the length of the descriptors.
+    ///
+    /// If it is shorter, validation continues with the first `wTotalLength`
+    /// bytes.
+    TotalLengthMismatch {
+        /// `wTotalLength` field of the configuration descriptor.
+        total_length: u16,
+
+        /// Actual length of the descriptors.
+        actual: usize,
+    },
+
+    /// A descriptor's `bLength` is less than 2, or longer than the rest of
+    /// the buffer.
+    ///
+    /// Descriptors after this point can't be located, and are not validated.
+    InvalidLength {
+        /// Offset of the descriptor.
+        offset: usize,
+
+        /// `bLength` field of the descriptor.
+        length: u8,
+    },
+
+    /// A descriptor's `bLength` is shorter than the minimum length for its
+    /// type.
+    ///
+    /// The descriptor is otherwise ignored.
+    DescriptorTooShort {
+        /// Offset of the descriptor.
+        offset: usize,
+
+        /// `bDescriptorType` field of the descriptor.
+        descriptor_type: u8,
+
+        /// `bLength` field of the descriptor.
+        length: u8,
+
+        /// Minimum length for a descriptor of this type.
+        min_length: u8,
+    },
+
+    /// `bNumInterfaces` doesn't match the number of distinct interface
+    /// numbers.
+    NumInterfacesMismatch {
+        /// `bNumInterfaces` field of the configuration descriptor.
+        declared: u8,
+
+        /// Number of distinct interface numbers.
+        actual: usize,
+    },
+
+    /// An interface's `bNumEndpoints` doesn't match the number of endpoint
+    /// descriptors that follow it.
+    NumEndpointsMismatch {
+        /// `bInterfaceNumber` of the interface.
+        interface_number: u8,
+
+        /// `bAlternateSetting` of the interface.
+        alternate_setting: u8,
+
+        /// `bNumEndpoints` field of the interface descriptor.
+        declared: u8,
+
+        /// Number of endpoint descriptors.
+        actual: usize,
+    },
+
+    /// An interface with the same `bInterfaceNumber` and `bAlternateSetting`
+    /// appears more than once.
+    DuplicateInterface {
+        /// Offset of the second interface descriptor.
+        offset: usize,
+
+        /// `bInterfaceNumber` of the interface.
+        interface_number: u8,
+
+        /// `bAlternateSetting` of the interface.
+        alternate_setting: u8,
+    },
+
+    /// An endpoint descriptor appears before any interface descriptor.
+    EndpointOutsideInterface {
+        /// Offset of the endpoint descriptor.
+        offset: usize,
+    },
+
+    /// An endpoint's `bEndpointAddress` is endpoint zero or has reserved bits
+    /// set.
+    InvalidEndpointAddress {
+        /// Offset of the endpoint descriptor.
+        offset: usize,
+
+        /// `bEndpointAddress` field of the endpoint descriptor.
+        address: u8,
+    },
+
+    /// An endpoint address is used twice in the same alternate setting, or
+    /// by two different interfaces.
+    DuplicateEndpointAddress {
+        /// Offset of the second endpoint descriptor.
+        offset: usize,
+
+        /// `bEndpointAddress` field of the endpoint descriptors.
+        address: u8,
+    },
+
+    /// An endpoint's `wMaxPacketSize` is not allowed for its transfer type
+    /// at the device's speed, or the transfer type is not allowed at all.
+    InvalidMaxPacketSize {
+        /// Offset of the endpoint descriptor.
+        offset: usize,
+
+        /// `bEndpointAddress` field of the endpoint descriptor.
+        address: u8,
+
+        /// Transfer type of the endpoint.
+        transfer_type: EndpointType,
+
+        /// `wMaxPacketSize` field of the endpoint descriptor.
+        max_packet_size_raw: u16,
+
+        /// Speed the endpoint was checked against.
+        speed: Speed,
+    },
+}
+
+impl Display for DescriptorProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DescriptorProblem::BufferTooShort { len } => write!(
+                f,
+                "buffer of {len} bytes is too short for a configuration descriptor"
+            ),
+            DescriptorProblem::NotConfiguration { descriptor_type } => write!(
+                f,
+                "descriptor type 0x{descriptor_type:02X} is not a configuration descriptor"
+            ),
+            DescriptorProblem::TotalLengthMismatch {
+                total_length,
+                actual,
+            } => write!(
+                f,
+                "wTotalLength is {total_length}, but descriptors are {actual} bytes"
+            ),
+            DescriptorProblem::InvalidLength { offset, length } => write!(
+                f,
+                "descriptor at offset {offset} has invalid bLength {length}"
+            ),
+            DescriptorProblem::DescriptorTooShort {
+                offset,
+                descriptor_type,
+                length,
+                min_length,
+            } => write!(
+                f,
+                "descriptor of type 0x{descriptor_type:02X} at offset {offset} has bLength {length}, minimum is {min_length}"
+            ),
+            DescriptorProblem::NumInterfacesMismatch { declared, actual } => write!(
+                f,
+                "bNumInterfaces is {declared}, but there are {actual} interfaces"
+            ),
+            DescriptorProblem::NumEndpointsMismatch {
+                interface_number,
+                alternate_setting,
+                declared,
+                actual,
+            } => write!(
+                f,
+                "interface {interface_number} alternate setting {alternate_setting} has bNumEndpoints {declared}, but {actual} endpoints"
+            ),
+            DescriptorProblem::DuplicateInterface {
+                offset,
+                interface_number,
+                alternate_setting,
+            } => write!(
+                f,
+                "interface {interface_number} alternate setting {alternate_setting} at offset {offset} is a duplicate"
+            ),
+            DescriptorProblem::EndpointOutsideInterface { offset } => write!(
+                f,
+                "endpoint at offset {offset} does not follow an interface"
+            ),
+            DescriptorProblem::InvalidEndpointAddress { offset, address } => write!(
+                f,
+                "endpoint at offset {offset} has invalid address 0x{address:02X}"
+            ),
+            DescriptorProblem::DuplicateEndpointAddress { offset, address } => write!(
+                f,
+                "endpoint at offset {offset} reuses address 0x{address:02X}"
+            ),
+            DescriptorProblem::InvalidMaxPacketSize {
+                offset,
+                address,
+                transfer_type,
+                max_packet_size_raw,
+                speed,
+            } => write!(
+                f,
+                "{transfer_type:?} endpoint 0x{address:02X} at offset {offset} has wMaxPacketSize 0x{max_packet_size_raw:04X}, which is invalid at {speed:?} speed"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DescriptorProblem {}
+
+/// Check a configuration descriptor and the descriptors that follow it for
+/// violations of the USB specification.
+///
+/// Pass the speed the device is operating at, if known, to check endpoint
+/// max packet sizes. Returns an empty `Vec` if no problems were found.
+///
+/// ```
+/// use nusb::descriptors::{validate_configuration, DescriptorProblem};
+/// let buf = [9, 2, 18, 0, 2, 1, 0, 0x80, 50, 9, 4, 0, 0, 0, 0xff, 0, 0, 0];
+/// assert_eq!(
+///     validate_configuration(&buf, None),
+///     [DescriptorProblem::NumInterfacesMismatch { declared: 2, actual: 1 }]
+/// );
+/// ```
+pub fn validate_configuration(buf: &[u8], speed: Option<Speed>) -> Vec<DescriptorProblem> {
+    let mut problems = Vec::new();
+
+    if buf.len() < DESCRIPTOR_LEN_CONFIGURATION as usize {
+        problems.push(DescriptorProblem::BufferTooShort { len: buf.len() });
+        return problems;
+    }
+
+    if buf[1] != DESCRIPTOR_TYPE_CONFIGURATION {
+        problems.push(DescriptorProblem::NotConfiguration {
+            descriptor_type: buf[1],
+        });
+        return problems;
+    }
+
+    let total_length = u16::from_le_bytes([buf[2], buf[3]]);
+    if total_length as usize != buf.len() {
+        problems.push(DescriptorProblem::TotalLengthMismatch {
+            total_length,
+            actual: buf.len(),
+        });
+    }
+    let declared = buf[4];
+    let buf = &buf[..buf.len().min(total_length as usize)];
+
+    let mut v = Validator {
+        speed,
+        problems,
+        interfaces: BTreeSet::new(),
+        endpoint_owners: BTreeMap::new(),
+        current: None,
+    };
+
+    let mut offset = 0;
+    while offset < buf.len() {
+        let desc = &buf[offset..];
+        let length = desc[0];
+        if length < 2 || length as usize > desc.len() {
+            v.problems
+                .push(DescriptorProblem::InvalidLength { offset, length });
+            break;
+        }
+        v.descriptor(offset, &desc[..length as usize]);
+        offset += length as usize;
+    }
+    v.finish_interface();
+
+    let actual = v
+        .interfaces
+        .iter()
+        .map(|&(n, _)| n)
+        .collect::<BTreeSet<_>>()
+        .len();
+    if declared as usize != actual {
+        v.problems
+            .push(DescriptorProblem::NumInterfacesMismatch { declared, actual });
+    }
+
+    v.problems
+}
+
+struct CurrentInterface {
+    interface_number: u8,
+    alternate_setting: u8,
+    declared_endpoints: u8,
+    num_endpoints: usize,
+    addresses: BTreeSet<u8>,
+}
+
+struct Validator {
+    speed: Option<Speed>,
+    problems: Vec<DescriptorProblem>,
+
+    /// Interface numbers and alternate settings seen so far.
+    interfaces: BTreeSet<(u8, u8)>,
+
+    /// Interface number that uses each endpoint address.
+    endpoint_owners: BTreeMap<u8, u8>,
+
+    current: Option<CurrentInterface>,
+}
+
+impl Validator {
+    fn descriptor(&mut self, offset: usize, desc: &[u8]) {
+        let descriptor_type = desc[1];
+        let min_length = match descriptor_type {
+            DESCRIPTOR_TYPE_CONFIGURATION => DESCRIPTOR_LEN_CONFIGURATION,
+            DESCRIPTOR_TYPE_INTERFACE => DESCRIPTOR_LEN_INTERFACE,
+            DESCRIPTOR_TYPE_ENDPOINT => DESCRIPTOR_LEN_ENDPOINT,
+            DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION => DESCRIPTOR_LEN_INTERFACE_ASSOCIATION,
+            _ => 2,
+        };
+
+        if desc.len() < min_length as usize {
+            self.problems.push(DescriptorProblem::DescriptorTooShort {
+                offset,
+                descriptor_type,
+                length: desc[0],
+                min_length,
+            });
+            return;
+        }
+
+        match descriptor_type {
+            DESCRIPTOR_TYPE_INTERFACE => self.interface(offset, desc),
+            DESCRIPTOR_TYPE_ENDPOINT => self.endpoint(offset, desc),
+            _ => {}
+        }
+    }
+
+    fn interface(&mut self, offset: usize, desc: &[u8]) {
+        self.finish_interface();
+
+        let interface_number = desc[2];
+        let alternate_setting = desc[3];
+        if !self
+            .interfaces
+            .insert((interface_number, alternate_setting))
+        {
+            self.problems.push(DescriptorProblem::DuplicateInterface {
+                offset,
+                interface_number,
+                alternate_setting,
+            });
+        }
+
+        self.current = Some(CurrentInterface {
+            interface_number,
+            alternate_setting,
+            declared_endpoints: desc[4],
+            num_endpoints: 0,
+            addresses: BTreeSet::new(),
+        });
+    }
+
+    fn finish_interface(&mut self) {
+        let Some(intf) = self.current.take() else {
+            return;
+        };
+
+        if intf.declared_endpoints as usize != intf.num_endpoints {
+            self.problems.push(DescriptorProblem::NumEndpointsMismatch {
+                interface_number: intf.interface_number,
+                alternate_setting: intf.alternate_setting,
+                declared: intf.declared_endpoints,
+                actual: intf.num_endpoints,
+            });
+        }
+    }
+
+    fn endpoint(&mut self, offset: usize, desc: &[u8]) {
+        let Some(intf) = &mut self.current else {
+            self.problems
+                .push(DescriptorProblem::EndpointOutsideInterface { offset });
+            return;
+        };
+
+        let address = desc[2];
+        if address & 0x0f == 0 || address & 0x70 != 0 {
+            self.problems
+                .push(DescriptorProblem::InvalidEndpointAddress { offset, address });
+        }
+
+        intf.num_endpoints += 1;
+        let duplicate_in_alt = !intf.addresses.insert(address);
+        let owner = *self
+            .endpoint_owners
+            .entry(address)
+            .or_insert(intf.interface_number);
+        if duplicate_in_alt || owner != intf.interface_number {
+            self.problems
+                .push(DescriptorProblem::DuplicateEndpointAddress { offset, address });
+        }
+
+        let transfer_type = match desc[3] & 0x03 {
+            0 => EndpointType::Control,
+            1 => EndpointType::Isochronous,
+            2 => EndpointType::Bulk,
+            _ => EndpointType::Interrupt,
+        };
+        let max_packet_size_raw = u16::from_le_bytes([desc[4], desc[5]]);
+        if let Some(speed) = self.speed {
+            if !valid_max_packet_size(transfer_type, max_packet_size_raw, speed) {
+                self.problems.push(DescriptorProblem::InvalidMaxPacketSize {
+                    offset,
+                    address,
+                    transfer_type,
+                    max_packet_size_raw,
+                    speed,
+                });
+            }
+        }
+    }
+}
+
+/// Check `wMaxPacketSize` against USB 2.0 section 5 and USB 3.2 section
+/// 9.6.6.
+fn valid_max_packet_size(transfer_type: EndpointType, raw: u16, speed: Speed) -> bool {
+    use EndpointType::*;
+
+    let size = raw & 0x07ff;
+    let additional = (raw >> 11) & 0x03;
+    let reserved = raw >> 13;
+
+    if reserved != 0 {
+        return false;
+    }
+
+    match speed {
+        Speed::Low => {
+            additional == 0
+                && match transfer_type {
+                    Control => size == 8,
+                    Interrupt => size <= 8,
+                    Bulk | Isochronous => false,
+                }
+        }
+        Speed::Full => {
+            additional == 0
+                && match transfer_type {
+                    Control | Bulk => matches!(size, 8 | 16 | 32 | 64),
+                    Interrupt => size <= 64,
+                    Isochronous => size <= 1023,
+                }
+        }
+        Speed::High => match transfer_type {
+            Control => additional == 0 && size == 64,
+            Bulk => additional == 0 && size == 512,
+            Interrupt | Isochronous => match additional {
+                0 => size <= 1024,
+                1 => (513..=1024).contains(&size),
+                2 => (683..=1024).contains(&size),
+                _ => false,
+            },
+        },
+        Speed::Super | Speed::SuperPlus => {
+            additional == 0
+                && match transfer_type {
+                    Control => size == 512,
+                    Bulk => size == 1024,
+                    Interrupt | Isochronous => (1..=1024).contains(&size),
+                }
+        }
+    }
+}
+
+#[test]
+fn test_valid() {
+    use super::{
+        ConfigurationBuilder, EndpointBuilder, InterfaceBuilder, SuperSpeedCompanionBuilder,
+    };
+
+    let buf = ConfigurationBuilder::new(1)
+        .interface(
+            InterfaceBuilder::new(0, 0)
+                .endpoint(EndpointBuilder::new(0x81, EndpointType::Interrupt).max_packet_size(8)),
+        )
+        .interface(InterfaceBuilder::new(1, 0))
+        .interface(
+            InterfaceBuilder::new(1, 1)
+                .endpoint(
+                    EndpointBuilder::new(0x82, EndpointType::Bulk)
+                        .max_packet_size(1024)
+                        .superspeed_companion(SuperSpeedCompanionBuilder::new()),
+                )
+                .endpoint(EndpointBuilder::new(0x02, EndpointType::Bulk).max_packet_size(1024)),
+        )
+        .build();
+
+    assert_eq!(validate_configuration(&buf, Some(Speed::Super)), []);
+    assert_eq!(
+        validate_configuration(&buf, Some(Speed::High)),
+        [
+            DescriptorProblem::InvalidMaxPacketSize {
+                offset: 43,
+                address: 0x82,
+                transfer_type: EndpointType::Bulk,
+                max_packet_size_raw: 1024,
+                speed: Speed::High,
+            },
+            DescriptorProblem::InvalidMaxPacketSize {
+                offset: 56,
+                address: 0x02,
+                transfer_type: EndpointType::Bulk,
+                max_packet_size_raw: 1024,
+                speed: Speed::High,
+            },
+        ]
+    );
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_problems() {
+    let buf = [
+        0x09, 0x02, 0x2a, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+        0x09, 0x04, 0x00, 0x00, 0x01, 0xff, 0x00, 0x00, 0x00,
+        0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00,
+        0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00,
+        0x09, 0x04, 0x01, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00,
+        0x07, 0x05, 0x00, 0x02, 0x40, 0x00, 0x00,
+        0x05, 0x05, 0x02, 0x02, 0x40,
+        0x01,
+    ];
+
+    let problems = validate_configuration(&buf, Some(Speed::Full));
+    assert_eq!(
+        problems,
+        [
+            DescriptorProblem::TotalLengthMismatch { total_length: 42, actual: 54 },
+            DescriptorProblem::DuplicateEndpointAddress { offset: 25, address: 0x81 },
+            DescriptorProblem::NumEndpointsMismatch {
+                interface_number: 0,
+                alternate_setting: 0,
+                declared: 1,
+                actual: 2,
+            },
+            DescriptorProblem::InvalidLength { offset: 41, length: 7 },
+            DescriptorProblem::NumInterfacesMismatch { declared: 1, actual: 2 },
+        ]
+    );
+    assert_eq!(
+        problems[0].to_string(),
+        "wTotalLength is 42, but descriptors are 54 bytes"
+    );
+
+    let mut buf = buf;
+    buf[2] = buf.len() as u8;
+    assert_eq!(
+        validate_configuration(&buf, None)[2..],
+        [
+            DescriptorProblem::InvalidEndpointAddress { offset: 41, address: 0x00 },
+            DescriptorProblem::DescriptorTooShort {
+                offset: 48,
+                descriptor_type: 0x05,
+                length: 5,
+                min_length: 7,
+            },
+            DescriptorProblem::InvalidLength { offset: 53, length: 1 },
+            DescriptorProblem::NumEndpointsMismatch {
+                interface_number: 1,
+                alternate_setting: 0,
+                declared: 0,
+                actual: 1,
+            },
+            DescriptorProblem::NumInterfacesMismatch { declared: 1, actual: 2 },
+        ]
+    );
+
+    assert_eq!(
+        validate_configuration(&[0x09, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], None),
+        [DescriptorProblem::NotConfiguration { descriptor_type: 0x04 }]
+    );
+}
+
+#[test]
+fn test_short_total_length() {
+    for total_length in 0..=8u16 {
+        let mut buf = [0x09, 0x02, 0x00, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32];
+        buf[2] = total_length as u8;
+        let problems = validate_configuration(&buf, None);
+        assert_eq!(
+            problems[0],
+            DescriptorProblem::TotalLengthMismatch {
+                total_length,
+                actual: 9
+            }
+        );
+        assert_eq!(
+            problems.last(),
+            Some(&DescriptorProblem::NumInterfacesMismatch {
+                declared: 1,
+                actual: 0
+            })
+        );
+    }
+}
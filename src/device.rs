@@ -1,17 +1,27 @@
 use crate::{
+    cdc,
     descriptors::{
         decode_string_descriptor, validate_string_descriptor, ActiveConfigurationError,
-        Configuration, InterfaceAltSetting, DESCRIPTOR_TYPE_STRING,
+        Configuration, InterfaceAltSetting, OwnedConfiguration, DESCRIPTOR_TYPE_STRING,
     },
+    hid,
+    hotplug::HotplugEvent,
     platform,
     transfer::{
-        Control, ControlIn, ControlOut, EndpointType, Queue, RequestBuffer, TransferError,
-        TransferFuture,
+        Control, ControlIn, ControlOut, ControlType, EndpointType, Queue, Recipient, RequestBuffer,
+        TransferError, TransferFuture,
     },
     DeviceInfo, Error,
 };
 use log::error;
-use std::{io::ErrorKind, sync::Arc, time::Duration};
+use std::{
+    cell::RefCell,
+    io::ErrorKind,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub(crate) const STANDARD_REQUEST_GET_DESCRIPTOR: u8 = 0x06;
 
 /// An opened USB device.
 ///
@@ -94,6 +104,50 @@ impl Device {
         Ok(())
     }
 
+    /// *(Linux-only)* Get the name of the kernel driver bound to the
+    /// specified interface, or `None` if no driver is bound.
+    ///
+    /// An interface claimed through usbfs, including by this library, is
+    /// reported as bound to `usbfs`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn kernel_driver(&self, interface: u8) -> Result<Option<String>, Error> {
+        self.backend.kernel_driver(interface)
+    }
+
+    /// *(Linux-only)* Unbind the kernel driver named `driver`, e.g. `usbhid`,
+    /// from the specified interface.
+    ///
+    /// Unlike [`detach_kernel_driver`][`Self::detach_kernel_driver`], this
+    /// fails with [`std::io::ErrorKind::NotFound`] and leaves the interface
+    /// alone if it is bound to a different driver. The check and the unbind
+    /// are a single operation, so a driver bound in between is not unbound.
+    /// Succeeds if no driver is bound.
+    ///
+    /// Fails with [`std::io::ErrorKind::InvalidInput`] if `driver` is not a
+    /// valid driver name.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn unbind_kernel_driver(&self, interface: u8, driver: &str) -> Result<(), Error> {
+        self.backend.unbind_kernel_driver(interface, driver)
+    }
+
+    /// *(Linux-only)* Bind the kernel driver named `driver`, e.g. `cdc_acm`,
+    /// to the specified interface.
+    ///
+    /// Unlike [`attach_kernel_driver`][`Self::attach_kernel_driver`], which
+    /// lets the kernel choose a driver, this binds the chosen driver by
+    /// writing to `/sys/bus/usb/drivers/<driver>/bind`. The interface must
+    /// not be bound to a driver, and the driver must support the device.
+    ///
+    /// Writing to sysfs usually requires root, or a udev rule granting
+    /// access. Fails with [`std::io::ErrorKind::Unsupported`] if the device
+    /// was not opened from sysfs, e.g. with [`Device::from_fd`], and with
+    /// [`std::io::ErrorKind::InvalidInput`] if `driver` is not a valid driver
+    /// name, such as one containing `/`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn bind_kernel_driver(&self, interface: u8, driver: &str) -> Result<(), Error> {
+        self.backend.bind_kernel_driver(interface, driver)
+    }
+
     /// Get information about the active configuration.
     ///
     /// This returns cached data and does not perform IO. However, it can fail if the
@@ -118,6 +172,28 @@ impl Device {
             .map(Configuration::new)
     }
 
+    /// Get information about the active configuration, as an
+    /// [`OwnedConfiguration`] that doesn't borrow the `Device`.
+    ///
+    /// See [`active_configuration`][`Self::active_configuration`].
+    pub fn active_configuration_owned(
+        &self,
+    ) -> Result<OwnedConfiguration, ActiveConfigurationError> {
+        self.active_configuration().map(OwnedConfiguration::from)
+    }
+
+    /// Get an iterator returning information about each configuration of the
+    /// device, as [`OwnedConfiguration`]s that don't borrow the `Device`.
+    ///
+    /// See [`configurations`][`Self::configurations`].
+    pub fn configurations_owned(&self) -> impl Iterator<Item = OwnedConfiguration> {
+        let configurations: Vec<_> = self
+            .configurations()
+            .map(OwnedConfiguration::from)
+            .collect();
+        configurations.into_iter()
+    }
+
     /// Set the device configuration.
     ///
     /// The argument is the desired configuration's `bConfigurationValue`
@@ -155,9 +231,6 @@ impl Device {
 
         #[cfg(not(target_os = "windows"))]
         {
-            const STANDARD_REQUEST_GET_DESCRIPTOR: u8 = 0x06;
-            use crate::transfer::{ControlType, Recipient};
-
             let mut buf = vec![0; 4096];
             let len = self.control_in_blocking(
                 Control {
@@ -232,7 +305,8 @@ impl Device {
     /// Reset the device, forcing it to re-enumerate.
     ///
     /// This `Device` will no longer be usable, and you should drop it and call
-    /// [`super::list_devices`] to find and re-open it again.
+    /// [`super::list_devices`] to find and re-open it again, or use
+    /// [`reset_and_reenumerate`][`Self::reset_and_reenumerate`] to wait for it.
     ///
     /// ### Platform-specific notes
     /// * Not supported on Windows
@@ -240,6 +314,114 @@ impl Device {
         self.backend.reset()
     }
 
+    /// Reset the device and wait for it to re-enumerate.
+    ///
+    /// Pass the [`DeviceInfo`] this device was opened from. After the reset,
+    /// this waits for a device to connect on the same port (bus ID and port
+    /// chain) and returns its `DeviceInfo`. If the port is not known, it
+    /// matches by serial number instead, and only if the device has no serial
+    /// number by vendor and product ID. Matching by port finds devices that
+    /// come back with a different address or product ID, such as a
+    /// bootloader. Use
+    /// [`reset_and_wait_for`][`Self::reset_and_wait_for`] to match the
+    /// device differently.
+    ///
+    /// Fails with [`ErrorKind::TimedOut`] if no matching device connects
+    /// within `timeout`.
+    ///
+    /// This `Device` will no longer be usable. Call [`DeviceInfo::open`] on the
+    /// result to open the device again.
+    ///
+    /// ### Platform-specific notes
+    /// * Not supported on Windows
+    /// * On Linux, the kernel resets a device in place without
+    ///   re-enumerating it if its descriptors did not change. In that case,
+    ///   this returns the device's current information without waiting.
+    pub fn reset_and_reenumerate(
+        &self,
+        info: &DeviceInfo,
+        timeout: Duration,
+    ) -> Result<DeviceInfo, Error> {
+        self.reset_and_wait(
+            timeout,
+            &mut |d| d.id() == info.id() && is_reenumerated(info, d),
+            &mut |d| is_reenumerated(info, d),
+        )
+    }
+
+    /// Reset the device and wait for a device selected by `matcher` to
+    /// connect.
+    ///
+    /// Like [`reset_and_reenumerate`][`Self::reset_and_reenumerate`], but
+    /// returns the first newly-connected device for which `matcher` returns
+    /// `true`.
+    ///
+    /// Fails with [`ErrorKind::TimedOut`] if no matching device connects
+    /// within `timeout`.
+    ///
+    /// ### Platform-specific notes
+    /// * Not supported on Windows
+    /// * On Linux, if the kernel resets the device in place without
+    ///   re-enumerating it, this returns the first currently-connected device
+    ///   for which `matcher` returns `true`.
+    pub fn reset_and_wait_for(
+        &self,
+        timeout: Duration,
+        mut matcher: impl FnMut(&DeviceInfo) -> bool,
+    ) -> Result<DeviceInfo, Error> {
+        let matcher = RefCell::new(&mut matcher);
+        self.reset_and_wait(timeout, &mut |d| (matcher.borrow_mut())(d), &mut |d| {
+            (matcher.borrow_mut())(d)
+        })
+    }
+
+    /// Reset the device, then find it with `in_place` if the OS reset it
+    /// without re-enumerating, or wait for a device matching `reconnected` to
+    /// connect.
+    fn reset_and_wait(
+        &self,
+        timeout: Duration,
+        in_place: &mut dyn FnMut(&DeviceInfo) -> bool,
+        reconnected: &mut dyn FnMut(&DeviceInfo) -> bool,
+    ) -> Result<DeviceInfo, Error> {
+        let deadline = Instant::now() + timeout;
+
+        // Start watching before the reset so the reconnection can't be missed.
+        let mut watch = crate::watch_devices()?;
+
+        match self.reset() {
+            // usbfs resets the device in place unless its descriptors
+            // changed, in which case the kernel re-enumerates it and the
+            // ioctl fails with ENODEV.
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Ok(()) => {
+                if let Some(d) = crate::list_devices()?.find(|d| in_place(d)) {
+                    return Ok(d);
+                }
+            }
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            Ok(()) => {
+                let _ = in_place;
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Err(e) if e.raw_os_error() == Some(libc::ENODEV) => {}
+            Err(e) => return Err(e),
+        }
+
+        while let Some(event) = watch.next_until(deadline) {
+            if let HotplugEvent::Connected(d) = event {
+                if reconnected(&d) {
+                    return Ok(d);
+                }
+            }
+        }
+
+        Err(Error::new(
+            ErrorKind::TimedOut,
+            "device did not re-enumerate before the timeout",
+        ))
+    }
+
     /// Synchronously perform a single **IN (device-to-host)** transfer on the default **control** endpoint.
     ///
     /// ### Platform-specific notes
@@ -559,6 +741,149 @@ impl Interface {
         self.backend.clear_halt(endpoint)
     }
 
+    /// Request the HID report descriptor of this interface.
+    ///
+    /// Pass the length from
+    /// [`HidDescriptor::report_descriptor_length`][crate::hid::HidDescriptor::report_descriptor_length],
+    /// and parse the result with
+    /// [`ReportDescriptor::parse`][crate::hid::ReportDescriptor::parse].
+    pub fn hid_get_report_descriptor(&self, length: u16) -> TransferFuture<ControlIn> {
+        self.control_in(ControlIn {
+            control_type: ControlType::Standard,
+            recipient: Recipient::Interface,
+            request: STANDARD_REQUEST_GET_DESCRIPTOR,
+            value: (hid::DESCRIPTOR_TYPE_REPORT as u16) << 8,
+            index: self.interface_number() as u16,
+            length,
+        })
+    }
+
+    /// Send a HID `GET_REPORT` request to read a report over the control
+    /// endpoint.
+    ///
+    /// Pass a `report_id` of `0` if the device does not use report IDs. When
+    /// it does, the response starts with the report ID byte.
+    pub fn hid_get_report(
+        &self,
+        report_type: hid::ReportType,
+        report_id: u8,
+        length: u16,
+    ) -> TransferFuture<ControlIn> {
+        self.control_in(ControlIn {
+            control_type: ControlType::Class,
+            recipient: Recipient::Interface,
+            request: hid::REQUEST_GET_REPORT,
+            value: ((report_type as u16) << 8) | report_id as u16,
+            index: self.interface_number() as u16,
+            length,
+        })
+    }
+
+    /// Send a HID `SET_REPORT` request to write a report over the control
+    /// endpoint.
+    ///
+    /// Pass a `report_id` of `0` if the device does not use report IDs. When
+    /// it does, `data` must start with the report ID byte.
+    pub fn hid_set_report(
+        &self,
+        report_type: hid::ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> TransferFuture<ControlOut<'static>> {
+        self.class_control_out(
+            hid::REQUEST_SET_REPORT,
+            ((report_type as u16) << 8) | report_id as u16,
+            data,
+        )
+    }
+
+    /// Send a HID `SET_IDLE` request to limit how often the device sends
+    /// input reports that have not changed.
+    ///
+    /// The `duration` is in units of 4 milliseconds, with `0` meaning that
+    /// reports are only sent when they change. A `report_id` of `0` applies
+    /// to all reports.
+    pub fn hid_set_idle(&self, report_id: u8, duration: u8) -> TransferFuture<ControlOut<'static>> {
+        self.class_control_out(
+            hid::REQUEST_SET_IDLE,
+            ((duration as u16) << 8) | report_id as u16,
+            &[],
+        )
+    }
+
+    /// Send a HID `SET_PROTOCOL` request to switch a boot device between the
+    /// boot protocol and the report protocol.
+    pub fn hid_set_protocol(&self, protocol: hid::Protocol) -> TransferFuture<ControlOut<'static>> {
+        self.class_control_out(hid::REQUEST_SET_PROTOCOL, protocol as u16, &[])
+    }
+
+    /// Send a CDC `SET_LINE_CODING` request to set the baud rate and
+    /// character format of a CDC-ACM communication interface.
+    pub fn cdc_set_line_coding(
+        &self,
+        line_coding: &cdc::LineCoding,
+    ) -> TransferFuture<ControlOut<'static>> {
+        self.class_control_out(cdc::REQUEST_SET_LINE_CODING, 0, &line_coding.to_bytes())
+    }
+
+    /// Send a CDC `GET_LINE_CODING` request to read the baud rate and
+    /// character format of a CDC-ACM communication interface.
+    ///
+    /// Parse the result with
+    /// [`LineCoding::from_bytes`][crate::cdc::LineCoding::from_bytes].
+    pub fn cdc_get_line_coding(&self) -> TransferFuture<ControlIn> {
+        self.control_in(ControlIn {
+            control_type: ControlType::Class,
+            recipient: Recipient::Interface,
+            request: cdc::REQUEST_GET_LINE_CODING,
+            value: 0,
+            index: self.interface_number() as u16,
+            length: cdc::LINE_CODING_LEN as u16,
+        })
+    }
+
+    /// Send a CDC `SET_CONTROL_LINE_STATE` request to set the DTR and RTS
+    /// signals of a CDC-ACM communication interface.
+    pub fn cdc_set_control_line_state(
+        &self,
+        dtr: bool,
+        rts: bool,
+    ) -> TransferFuture<ControlOut<'static>> {
+        self.class_control_out(
+            cdc::REQUEST_SET_CONTROL_LINE_STATE,
+            dtr as u16 | (rts as u16) << 1,
+            &[],
+        )
+    }
+
+    /// Send a CDC `SEND_BREAK` request to transmit a break of `duration`
+    /// milliseconds on a CDC-ACM communication interface.
+    ///
+    /// A `duration` of `0xFFFF` holds the break until another request with a
+    /// `duration` of `0` ends it.
+    pub fn cdc_send_break(&self, duration: u16) -> TransferFuture<ControlOut<'static>> {
+        self.class_control_out(cdc::REQUEST_SEND_BREAK, duration, &[])
+    }
+
+    fn class_control_out(
+        &self,
+        request: u8,
+        value: u16,
+        data: &[u8],
+    ) -> TransferFuture<ControlOut<'static>> {
+        // The data is copied on submit, so the future doesn't borrow it.
+        let mut t = self.backend.make_transfer(0, EndpointType::Control);
+        t.submit::<ControlOut>(ControlOut {
+            control_type: ControlType::Class,
+            recipient: Recipient::Interface,
+            request,
+            value,
+            index: self.interface_number() as u16,
+            data,
+        });
+        TransferFuture::new(t)
+    }
+
     /// Get the interface number.
     pub fn interface_number(&self) -> u8 {
         self.backend.interface_number
@@ -584,6 +909,73 @@ impl Interface {
     }
 }
 
+/// Whether `new` could be `old` after a reset.
+fn is_reenumerated(old: &DeviceInfo, new: &DeviceInfo) -> bool {
+    ReenumerationKey::new(old).matches(&ReenumerationKey::new(new))
+}
+
+/// The properties of a [`DeviceInfo`] used to recognize a device after it
+/// re-enumerates.
+struct ReenumerationKey<'a> {
+    bus_id: &'a str,
+    port_chain: &'a [u8],
+    serial_number: Option<&'a str>,
+    vendor_id: u16,
+    product_id: u16,
+}
+
+impl<'a> ReenumerationKey<'a> {
+    fn new(info: &'a DeviceInfo) -> Self {
+        ReenumerationKey {
+            bus_id: info.bus_id(),
+            port_chain: info.port_chain(),
+            serial_number: info.serial_number(),
+            vendor_id: info.vendor_id(),
+            product_id: info.product_id(),
+        }
+    }
+
+    /// Match by the most specific property `self` has: the port if it is
+    /// known, else the serial number, else the vendor and product ID.
+    fn matches(&self, new: &ReenumerationKey) -> bool {
+        if !self.port_chain.is_empty() {
+            new.bus_id == self.bus_id && new.port_chain == self.port_chain
+        } else if self.serial_number.is_some() {
+            new.serial_number == self.serial_number
+        } else {
+            new.vendor_id == self.vendor_id && new.product_id == self.product_id
+        }
+    }
+}
+
+#[test]
+fn test_reenumeration_key() {
+    let key = |bus_id, port_chain, serial_number, product_id| ReenumerationKey {
+        bus_id,
+        port_chain,
+        serial_number,
+        vendor_id: 0x1209,
+        product_id,
+    };
+
+    // Known port: only the same port matches, even with different IDs.
+    let old = key("1", &[2, 1], Some("A"), 0x0001);
+    assert!(old.matches(&key("1", &[2, 1], None, 0x0002)));
+    assert!(!old.matches(&key("1", &[2, 2], Some("A"), 0x0001)));
+    assert!(!old.matches(&key("2", &[2, 1], Some("A"), 0x0001)));
+
+    // Unknown port: match by serial number, not IDs.
+    let old = key("1", &[], Some("A"), 0x0001);
+    assert!(old.matches(&key("1", &[3], Some("A"), 0x0001)));
+    assert!(!old.matches(&key("1", &[3], Some("B"), 0x0001)));
+    assert!(!old.matches(&key("1", &[3], None, 0x0001)));
+
+    // Neither: fall back to vendor and product ID.
+    let old = key("1", &[], None, 0x0001);
+    assert!(old.matches(&key("2", &[3], Some("B"), 0x0001)));
+    assert!(!old.matches(&key("2", &[3], None, 0x0002)));
+}
+
 #[test]
 fn assert_send_sync() {
     fn require_send_sync<T: Send + Sync>() {}
@@ -4,10 +4,20 @@ use std::ffi::{OsStr, OsString};
 #[cfg(any(target_os = "linux", target_os = "android"))]
 use crate::platform::SysfsPath;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::{
+    descriptors::{
+        parse_concatenated_config_descriptors, Configuration, Descriptor, DeviceDescriptor,
+        DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_TYPE_DEVICE,
+    },
+    transfer::{Direction, EndpointType},
+};
 use crate::{Device, Error};
 
 /// Opaque device identifier
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct DeviceId(pub(crate) crate::platform::DeviceId);
 
 /// Information about a device that can be obtained without opening it.
@@ -20,14 +30,30 @@ pub struct DeviceId(pub(crate) crate::platform::DeviceId);
 ///     * Linux: `sysfs_path`
 ///     * Windows: `instance_id`, `parent_instance_id`, `port_number`, `driver`
 ///     * macOS: `registry_id`, `location_id`
+///
+/// With the `serde` feature enabled, `DeviceInfo` can be serialized and
+/// deserialized, e.g. to send a device list to another process. The format
+/// includes the platform-specific fields, and is not stable across
+/// platforms or versions of this library. On Linux, the sysfs path is
+/// serialized but not deserialized, so that a deserialized `DeviceInfo`
+/// can't direct reads and writes to arbitrary files: it is detached from
+/// sysfs, and methods that access sysfs fail with
+/// [`std::io::ErrorKind::Unsupported`].
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceInfo {
     #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
     pub(crate) path: SysfsPath,
 
     #[cfg(any(target_os = "linux", target_os = "android"))]
     pub(crate) busnum: u8,
 
+    /// Device descriptor followed by configuration descriptors, or empty if
+    /// they couldn't be read.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) descriptors: Vec<u8>,
+
     #[cfg(target_os = "windows")]
     pub(crate) instance_id: OsString,
 
@@ -106,11 +132,137 @@ impl DeviceInfo {
     }
 
     /// *(Linux-only)* Sysfs path for the device.
+    ///
+    /// This is empty if sysfs is unavailable, such as in a container where
+    /// only `/dev/bus/usb` is accessible. Devices are then listed from their
+    /// usbfs device nodes, and the string descriptors, and on kernels older
+    /// than 5.2 the port chain and speed, are unavailable.
     #[cfg(target_os = "linux")]
     pub fn sysfs_path(&self) -> &std::path::Path {
         &self.path.0
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn sysfs(&self) -> Result<&SysfsPath, Error> {
+        if self.path.0.as_os_str().is_empty() {
+            return Err(Error::new(
+                std::io::ErrorKind::Unsupported,
+                "device was not enumerated from sysfs",
+            ));
+        }
+        Ok(&self.path)
+    }
+
+    /// *(Linux-only)* Read and parse a sysfs attribute of the device.
+    ///
+    /// This provides access to attributes without a dedicated method. The
+    /// value is read from sysfs each time, and is trimmed of whitespace
+    /// before being parsed.
+    ///
+    /// `attr` must be the name of a file in the device's sysfs directory.
+    /// Names containing `/` fail with [`std::io::ErrorKind::InvalidInput`].
+    ///
+    /// ```no_run
+    /// # let device = nusb::list_devices().unwrap().next().unwrap();
+    /// let max_power: String = device.sysfs_attr("bMaxPower").unwrap();
+    /// let avoid_reset: u8 = device.sysfs_attr("avoid_reset_quirk").unwrap();
+    /// ```
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn sysfs_attr<T: std::str::FromStr>(&self, attr: &str) -> Result<T, Error> {
+        crate::platform::check_attr_name(attr)?;
+        Ok(self.sysfs()?.read_attr(attr)?)
+    }
+
+    /// *(Linux-only)* Write a sysfs attribute of the device.
+    ///
+    /// Writing to sysfs usually requires root, or a udev rule granting
+    /// access. As with [`sysfs_attr`][`Self::sysfs_attr`], `attr` can't
+    /// contain `/`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_sysfs_attr(&self, attr: &str, value: &str) -> Result<(), Error> {
+        crate::platform::check_attr_name(attr)?;
+        self.sysfs()?.write_attr(attr, value)
+    }
+
+    /// *(Linux-only)* List the downstream ports of a hub.
+    ///
+    /// This is empty for devices that aren't hubs. The ports are read from
+    /// sysfs each time, in order of port number.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn ports(&self) -> Result<Vec<PortInfo>, Error> {
+        crate::platform::list_ports(self.sysfs()?)
+    }
+
+    /// *(Linux-only)* Whether the device can be unplugged, from the
+    /// `removable` sysfs attribute.
+    ///
+    /// This is `None` if the port the device is connected to doesn't say,
+    /// which is common for ports without ACPI or device tree information.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn removable(&self) -> Result<Option<bool>, Error> {
+        Ok(match &self.sysfs_attr::<String>("removable")?[..] {
+            "removable" => Some(true),
+            "fixed" => Some(false),
+            _ => None,
+        })
+    }
+
+    /// *(Linux-only)* Maximum power consumption of the active configuration
+    /// in milliamps, from the `bMaxPower` sysfs attribute.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn max_power_ma(&self) -> Result<u16, Error> {
+        let path = self.sysfs()?;
+        Ok(path.parse_attr("bMaxPower", |s| s.trim_end_matches("mA").parse::<u16>())?)
+    }
+
+    /// *(Linux-only)* The string descriptor of the active configuration, if
+    /// any, from the `configuration` sysfs attribute.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn configuration_string(&self) -> Result<Option<String>, Error> {
+        Ok(Some(self.sysfs_attr::<String>("configuration")?).filter(|s| !s.is_empty()))
+    }
+
+    /// *(Linux-only)* Whether a SuperSpeed device supports Latency Tolerance
+    /// Messaging, from the `ltm_capable` sysfs attribute.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn ltm_capable(&self) -> Result<bool, Error> {
+        Ok(self.sysfs_attr::<String>("ltm_capable")? == "yes")
+    }
+
+    /// *(Linux-only)* Quirk flags the kernel applies to the device, from the
+    /// `quirks` sysfs attribute.
+    ///
+    /// See `USB_QUIRK_*` in the kernel's `include/linux/usb/quirks.h`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn quirks(&self) -> Result<u32, Error> {
+        Ok(self.sysfs()?.read_attr_hex("quirks")?)
+    }
+
+    /// *(Linux-only)* Whether the kernel avoids resetting the device, from
+    /// the `avoid_reset_quirk` sysfs attribute.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn avoid_reset_quirk(&self) -> Result<bool, Error> {
+        Ok(self.sysfs_attr::<u8>("avoid_reset_quirk")? != 0)
+    }
+
+    /// *(Linux-only)* Set whether the kernel avoids resetting the device.
+    ///
+    /// Some devices misbehave after a reset, e.g. by switching modes.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_avoid_reset_quirk(&self, avoid_reset: bool) -> Result<(), Error> {
+        self.set_sysfs_attr("avoid_reset_quirk", if avoid_reset { "1" } else { "0" })
+    }
+
+    /// *(Linux-only)* Number of receive and transmit lanes, from the
+    /// `rx_lanes` and `tx_lanes` sysfs attributes.
+    ///
+    /// This is `(1, 1)` except for SuperSpeedPlus Gen 2x2 devices, and
+    /// requires Linux 4.18 or later.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn lanes(&self) -> Result<(u8, u8), Error> {
+        Ok((self.sysfs_attr("rx_lanes")?, self.sysfs_attr("tx_lanes")?))
+    }
+
     /// *(Linux-only)* Bus number.
     ///
     /// On Linux, the `bus_id` is an integer and this provides the value as `u8`.
@@ -119,6 +271,94 @@ impl DeviceInfo {
         self.busnum
     }
 
+    /// *(Linux-only)* Whether the device is authorized for use.
+    ///
+    /// The kernel doesn't configure an unauthorized device or bind drivers
+    /// to it. Unlike most `DeviceInfo` methods, this reads the current value
+    /// from sysfs.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn authorized(&self) -> Result<bool, Error> {
+        Ok(self.sysfs()?.read_attr::<u8>("authorized")? != 0)
+    }
+
+    /// *(Linux-only)* Authorize or deauthorize the device.
+    ///
+    /// Deauthorizing a device unbinds its drivers and unconfigures it, and
+    /// authorizing it configures it and binds drivers again. New devices can
+    /// be left unauthorized until allowed with
+    /// [`BusInfo::set_authorized_default`], so that they can be checked,
+    /// e.g. against a [`DeviceFilter`][`crate::DeviceFilter`], as they
+    /// connect.
+    ///
+    /// Writing to sysfs usually requires root, or a udev rule granting
+    /// access.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_authorized(&self, authorized: bool) -> Result<(), Error> {
+        self.sysfs()?
+            .write_attr("authorized", if authorized { "1" } else { "0" })
+    }
+
+    /// *(Linux-only)* Whether the specified interface of the active
+    /// configuration is authorized for use.
+    ///
+    /// Drivers aren't bound to an unauthorized interface. This reads the
+    /// current value from sysfs.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn interface_authorized(&self, interface: u8) -> Result<bool, Error> {
+        Ok(self
+            .sysfs()?
+            .interface(interface)?
+            .read_attr::<u8>("authorized")?
+            != 0)
+    }
+
+    /// *(Linux-only)* Authorize or deauthorize the specified interface of
+    /// the active configuration.
+    ///
+    /// Deauthorizing an interface unbinds its driver. This allows blocking
+    /// individual functions of a composite device, such as a keyboard
+    /// interface on a storage device.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_interface_authorized(&self, interface: u8, authorized: bool) -> Result<(), Error> {
+        self.sysfs()?
+            .interface(interface)?
+            .write_attr("authorized", if authorized { "1" } else { "0" })
+    }
+
+    /// *(Linux-only)* The device descriptor, as cached by the kernel.
+    ///
+    /// This is read from sysfs when the device is listed, which unlike opening
+    /// the device doesn't require permission. Returns `None` if it couldn't
+    /// be read.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn device_descriptor(&self) -> Option<DeviceDescriptor<'_>> {
+        Descriptor::new(&self.descriptors)
+            .filter(|d| {
+                d.descriptor_type() == DESCRIPTOR_TYPE_DEVICE
+                    && d.descriptor_len() >= DESCRIPTOR_LEN_DEVICE as usize
+            })
+            .map(|_| DeviceDescriptor::new(&self.descriptors))
+    }
+
+    /// *(Linux-only)* Get an iterator returning information about each
+    /// configuration of the device, as cached by the kernel.
+    ///
+    /// Like [`device_descriptor`][`Self::device_descriptor`], this doesn't
+    /// require permission to open the device. It returns the same
+    /// information as [`Device::configurations`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn configurations(&self) -> impl Iterator<Item = Configuration<'_>> {
+        self.device_descriptor()
+            .map(|_| {
+                parse_concatenated_config_descriptors(
+                    &self.descriptors[DESCRIPTOR_LEN_DEVICE as usize..],
+                )
+            })
+            .into_iter()
+            .flatten()
+            .map(Configuration::new)
+    }
+
     /// *(Windows-only)* Instance ID path of this device
     #[cfg(target_os = "windows")]
     pub fn instance_id(&self) -> &OsStr {
@@ -340,6 +580,8 @@ impl std::fmt::Debug for DeviceInfo {
 
 /// USB connection speed
 #[derive(Copy, Clone, Eq, PartialOrd, Ord, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 #[non_exhaustive]
 pub enum Speed {
     /// Low speed (1.5 Mbit)
@@ -355,11 +597,11 @@ pub enum Speed {
     Super,
 
     /// Super speed (10000 Mbit)
+    #[cfg_attr(feature = "serde", serde(rename = "super+"))]
     SuperPlus,
 }
 
 impl Speed {
-    #[allow(dead_code)] // not used on all platforms
     pub(crate) fn from_str(s: &str) -> Option<Self> {
         match s {
             "low" | "1.5" => Some(Speed::Low),
@@ -370,16 +612,39 @@ impl Speed {
             _ => None,
         }
     }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Speed::Low => "low",
+            Speed::Full => "full",
+            Speed::High => "high",
+            Speed::Super => "super",
+            Speed::SuperPlus => "super+",
+        }
+    }
 }
 
 /// Summary information about a device's interface, available before opening a device.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterfaceInfo {
     pub(crate) interface_number: u8,
     pub(crate) class: u8,
     pub(crate) subclass: u8,
     pub(crate) protocol: u8,
     pub(crate) interface_string: Option<String>,
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) alt_setting: u8,
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) num_endpoints: u8,
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) endpoints: Vec<EndpointInfo>,
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) driver: Option<String>,
 }
 
 impl InterfaceInfo {
@@ -407,23 +672,306 @@ impl InterfaceInfo {
     pub fn interface_string(&self) -> Option<&str> {
         self.interface_string.as_deref()
     }
+
+    /// *(Linux-only)* The currently selected alternate setting, from the
+    /// `bAlternateSetting` interface descriptor field.
+    ///
+    /// The class codes and endpoints are those of this alternate setting.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn alt_setting(&self) -> u8 {
+        self.alt_setting
+    }
+
+    /// *(Linux-only)* Number of endpoints of the current alternate setting,
+    /// excluding endpoint 0, from the `bNumEndpoints` interface descriptor
+    /// field.
+    ///
+    /// This is the count declared by the device, which may differ from the
+    /// number of [`endpoints`][`Self::endpoints`] if some of them could not
+    /// be read.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn num_endpoints(&self) -> u8 {
+        self.num_endpoints
+    }
+
+    /// *(Linux-only)* Endpoints of the current alternate setting.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn endpoints(&self) -> impl Iterator<Item = &EndpointInfo> {
+        self.endpoints.iter()
+    }
+
+    /// *(Linux-only)* Name of the kernel driver bound to the interface, if
+    /// any, e.g. `usbhid` or `usbfs` if claimed through `nusb`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
 }
 
 // Not derived so that we can format some fields in hex
 impl std::fmt::Debug for InterfaceInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("InterfaceInfo")
-            .field("interface_number", &self.interface_number)
+        let mut s = f.debug_struct("InterfaceInfo");
+        s.field("interface_number", &self.interface_number)
             .field("class", &format_args!("0x{:02X}", self.class))
             .field("subclass", &format_args!("0x{:02X}", self.subclass))
             .field("protocol", &format_args!("0x{:02X}", self.protocol))
-            .field("interface_string", &self.interface_string)
+            .field("interface_string", &self.interface_string);
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            s.field("alt_setting", &self.alt_setting);
+            s.field("num_endpoints", &self.num_endpoints);
+            s.field("endpoints", &self.endpoints);
+            s.field("driver", &self.driver);
+        }
+
+        s.finish()
+    }
+}
+
+/// *(Linux-only)* Summary information about an endpoint of an interface,
+/// available before opening a device.
+///
+/// Found in [`InterfaceInfo::endpoints`].
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EndpointInfo {
+    pub(crate) address: u8,
+    pub(crate) attributes: u8,
+    pub(crate) max_packet_size_raw: u16,
+    pub(crate) interval: u8,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl EndpointInfo {
+    /// Endpoint address, from the `bEndpointAddress` descriptor field.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// The endpoint's direction.
+    pub fn direction(&self) -> Direction {
+        match self.address & 0x80 {
+            0 => Direction::Out,
+            _ => Direction::In,
+        }
+    }
+
+    /// Raw value of the `bmAttributes` descriptor field.
+    pub fn attributes(&self) -> u8 {
+        self.attributes
+    }
+
+    /// The endpoint's transfer type.
+    pub fn transfer_type(&self) -> EndpointType {
+        match self.attributes & 0x03 {
+            0 => EndpointType::Control,
+            1 => EndpointType::Isochronous,
+            2 => EndpointType::Bulk,
+            _ => EndpointType::Interrupt,
+        }
+    }
+
+    /// Maximum packet size in bytes.
+    pub fn max_packet_size(&self) -> usize {
+        (self.max_packet_size_raw & ((1 << 11) - 1)) as usize
+    }
+
+    /// Raw value of the `wMaxPacketSize` descriptor field.
+    pub fn max_packet_size_raw(&self) -> u16 {
+        self.max_packet_size_raw
+    }
+
+    /// Polling interval in frames or microframes, from the `bInterval`
+    /// descriptor field.
+    pub fn interval(&self) -> u8 {
+        self.interval
+    }
+}
+
+// Not derived so that we can format some fields in hex
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl std::fmt::Debug for EndpointInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EndpointInfo")
+            .field("address", &format_args!("0x{:02X}", self.address))
+            .field("direction", &self.direction())
+            .field("transfer_type", &self.transfer_type())
+            .field("max_packet_size", &self.max_packet_size())
+            .field("interval", &self.interval)
             .finish()
     }
 }
 
+/// *(Linux-only)* A downstream port of a hub, from its `*-port*` sysfs
+/// directory.
+///
+/// Found with [`DeviceInfo::ports`].
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone, Debug)]
+pub struct PortInfo {
+    pub(crate) path: SysfsPath,
+    pub(crate) port_number: u8,
+    pub(crate) connect_type: PortConnectType,
+    pub(crate) location: Option<u32>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl PortInfo {
+    /// Sysfs path for the port, e.g. `/sys/bus/usb/devices/1-1/1-1:1.0/1-1-port2`.
+    pub fn sysfs_path(&self) -> &std::path::Path {
+        &self.path.0
+    }
+
+    /// Port number on the hub, starting from 1.
+    ///
+    /// This is the number that appears in the
+    /// [`port_chain`][`DeviceInfo::port_chain`] of a device connected to the
+    /// port.
+    pub fn port_number(&self) -> u8 {
+        self.port_number
+    }
+
+    /// How the port is connected, as described by the platform firmware.
+    pub fn connect_type(&self) -> PortConnectType {
+        self.connect_type
+    }
+
+    /// Location of the port as assigned by the platform firmware, from the
+    /// `location` sysfs attribute.
+    ///
+    /// Ports sharing a location are the same physical connector, e.g. the
+    /// USB 2 and USB 3 ports of a USB 3 connector.
+    pub fn location(&self) -> Option<u32> {
+        self.location
+    }
+
+    /// The port sharing a connector with this port on the companion hub,
+    /// e.g. the SuperSpeed port of a High Speed port, from the `peer` sysfs
+    /// link.
+    pub fn peer(&self) -> Option<PortInfo> {
+        crate::platform::probe_port(SysfsPath(self.path.0.join("peer").canonicalize().ok()?))
+    }
+
+    /// Number of over-current events on the port since the hub was
+    /// connected, from the `over_current_count` sysfs attribute.
+    ///
+    /// This reads the current value from sysfs.
+    pub fn over_current_count(&self) -> Result<u32, Error> {
+        Ok(self.path.read_attr("over_current_count")?)
+    }
+
+    /// Whether the port is disabled, from the `disable` sysfs attribute.
+    ///
+    /// This reads the current value from sysfs.
+    pub fn disabled(&self) -> Result<bool, Error> {
+        Ok(self.path.read_attr::<u8>("disable")? != 0)
+    }
+
+    /// Disable or enable the port.
+    ///
+    /// A disabled port turns off and disconnects the device connected to it,
+    /// without losing power to the hub. This requires Linux 5.8 or later,
+    /// and writing to sysfs usually requires root, or a udev rule granting
+    /// access.
+    pub fn set_disabled(&self, disabled: bool) -> Result<(), Error> {
+        self.path
+            .write_attr("disable", if disabled { "1" } else { "0" })
+    }
+
+    /// Whether the port is quarantined because the device connected to it
+    /// repeatedly failed to enumerate, from the `quarantine` sysfs
+    /// attribute.
+    ///
+    /// This reads the current value from sysfs, and requires Linux 6.9 or
+    /// later.
+    pub fn quarantined(&self) -> Result<bool, Error> {
+        Ok(self.path.read_attr::<u8>("quarantine")? != 0)
+    }
+}
+
+/// *(Linux-only)* Which devices connected to a bus are authorized when they
+/// connect, from the `authorized_default` sysfs attribute.
+///
+/// See [`BusInfo::authorized_default`].
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum AuthorizedDefault {
+    /// No devices are authorized.
+    None,
+
+    /// All devices are authorized.
+    All,
+
+    /// Only devices connected to internal ports, as described by the
+    /// platform firmware, are authorized.
+    Internal,
+
+    /// All devices are authorized except wireless USB devices. This is the
+    /// kernel's default.
+    Wired,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl AuthorizedDefault {
+    pub(crate) fn from_value(value: i8) -> Option<AuthorizedDefault> {
+        match value {
+            0 => Some(AuthorizedDefault::None),
+            1 => Some(AuthorizedDefault::All),
+            2 => Some(AuthorizedDefault::Internal),
+            -1 => Some(AuthorizedDefault::Wired),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn value(self) -> i8 {
+        match self {
+            AuthorizedDefault::None => 0,
+            AuthorizedDefault::All => 1,
+            AuthorizedDefault::Internal => 2,
+            AuthorizedDefault::Wired => -1,
+        }
+    }
+}
+
+/// *(Linux-only)* How a hub port is connected, from the `connect_type`
+/// sysfs attribute.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[non_exhaustive]
+pub enum PortConnectType {
+    /// A user-accessible connector.
+    Hotplug,
+
+    /// Connected to a device that can't be unplugged, e.g. a built-in
+    /// camera.
+    Hardwired,
+
+    /// Not connected to anything.
+    NotUsed,
+
+    /// Not described by the platform firmware.
+    Unknown,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl PortConnectType {
+    pub(crate) fn from_str(s: &str) -> PortConnectType {
+        match s {
+            "hotplug" => PortConnectType::Hotplug,
+            "hardwired" => PortConnectType::Hardwired,
+            "not used" => PortConnectType::NotUsed,
+            _ => PortConnectType::Unknown,
+        }
+    }
+}
+
 /// USB host controller type
 #[derive(Copy, Clone, Eq, PartialOrd, Ord, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum UsbControllerType {
     /// xHCI controller (USB 3.0+)
@@ -464,14 +1012,19 @@ impl UsbControllerType {
 /// Information about a system USB bus.
 ///
 /// Platform-specific fields:
-/// * Linux: `path`, `parent_path`, `busnum`, `root_hub`
+/// * Linux: `path`, `parent_path`, `busnum`, `root_hub`, `pci_vendor_id`, `pci_device_id`, `num_ports`, `companion_busnum`
 /// * Windows: `instance_id`, `parent_instance_id`, `location_paths`, `devinst`, `root_hub_description`
 /// * macOS: `registry_id`, `location_id`, `name`, `provider_class_name`, `class_name`
+///
+/// As with [`DeviceInfo`], a deserialized `BusInfo` is detached from sysfs.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BusInfo {
     #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
     pub(crate) path: SysfsPath,
 
     #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
     pub(crate) parent_path: SysfsPath,
 
     /// The phony root hub device
@@ -481,6 +1034,18 @@ pub struct BusInfo {
     #[cfg(any(target_os = "linux", target_os = "android"))]
     pub(crate) busnum: u8,
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) pci_vendor_id: Option<u16>,
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) pci_device_id: Option<u16>,
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) num_ports: Option<u8>,
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) companion_busnum: Option<u8>,
+
     #[cfg(target_os = "windows")]
     pub(crate) instance_id: OsString,
 
@@ -522,11 +1087,24 @@ pub struct BusInfo {
 
 impl BusInfo {
     /// *(Linux-only)* Sysfs path for the bus.
+    ///
+    /// This is empty if the `BusInfo` was deserialized.
     #[cfg(any(target_os = "linux", target_os = "android"))]
     pub fn sysfs_path(&self) -> &std::path::Path {
         &self.path.0
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn sysfs(&self) -> Result<&SysfsPath, Error> {
+        if self.path.0.as_os_str().is_empty() {
+            return Err(Error::new(
+                std::io::ErrorKind::Unsupported,
+                "bus is detached from sysfs",
+            ));
+        }
+        Ok(&self.path)
+    }
+
     /// *(Linux-only)* Sysfs path for the parent controller
     #[cfg(any(target_os = "linux", target_os = "android"))]
     pub fn parent_sysfs_path(&self) -> &std::path::Path {
@@ -547,6 +1125,95 @@ impl BusInfo {
         &self.root_hub
     }
 
+    /// *(Linux-only)* PCI vendor ID of the host controller, or `None` if it
+    /// isn't a PCI device.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn pci_vendor_id(&self) -> Option<u16> {
+        self.pci_vendor_id
+    }
+
+    /// *(Linux-only)* PCI device ID of the host controller, or `None` if it
+    /// isn't a PCI device.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn pci_device_id(&self) -> Option<u16> {
+        self.pci_device_id
+    }
+
+    /// *(Linux-only)* Number of ports on the root hub.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn num_ports(&self) -> Option<u8> {
+        self.num_ports
+    }
+
+    /// *(Linux-only)* Highest speed supported by the bus.
+    ///
+    /// Devices connected to the bus may use this or any lower speed, except
+    /// that on an xHCI controller, SuperSpeed devices use the
+    /// [companion bus][`Self::companion_busnum`] and slower devices use the
+    /// High Speed bus.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn max_speed(&self) -> Option<Speed> {
+        self.root_hub.speed
+    }
+
+    /// *(Linux-only)* Bus number of the other bus of the same host
+    /// controller.
+    ///
+    /// An xHCI controller appears as two buses: one for USB 2 and one for
+    /// USB 3 SuperSpeed devices, each with a root hub. Their ports are
+    /// paired, see [`PortInfo::peer`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn companion_busnum(&self) -> Option<u8> {
+        self.companion_busnum
+    }
+
+    /// *(Linux-only)* Read and parse a sysfs attribute of the bus's root hub.
+    ///
+    /// See [`DeviceInfo::sysfs_attr`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn sysfs_attr<T: std::str::FromStr>(&self, attr: &str) -> Result<T, Error> {
+        crate::platform::check_attr_name(attr)?;
+        Ok(self.sysfs()?.read_attr(attr)?)
+    }
+
+    /// *(Linux-only)* Write a sysfs attribute of the bus's root hub.
+    ///
+    /// Writing to sysfs usually requires root, or a udev rule granting
+    /// access.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_sysfs_attr(&self, attr: &str, value: &str) -> Result<(), Error> {
+        crate::platform::check_attr_name(attr)?;
+        self.sysfs()?.write_attr(attr, value)
+    }
+
+    /// *(Linux-only)* Which devices connected to the bus are authorized
+    /// when they connect.
+    ///
+    /// Devices that aren't are left unauthorized until allowed with
+    /// [`DeviceInfo::set_authorized`]. This reads the current value from
+    /// sysfs.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn authorized_default(&self) -> Result<AuthorizedDefault, Error> {
+        let value = self.sysfs()?.read_attr::<i8>("authorized_default")?;
+        AuthorizedDefault::from_value(value).ok_or_else(|| {
+            Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("unknown authorized_default value {value}"),
+            )
+        })
+    }
+
+    /// *(Linux-only)* Set which devices connected to the bus are authorized
+    /// when they connect.
+    ///
+    /// This only affects devices connected afterwards. Writing to sysfs
+    /// usually requires root, or a udev rule granting access.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_authorized_default(&self, authorized: AuthorizedDefault) -> Result<(), Error> {
+        self.sysfs()?
+            .write_attr("authorized_default", &authorized.value().to_string())
+    }
+
     /// *(Windows-only)* Instance ID path of this device
     #[cfg(target_os = "windows")]
     pub fn instance_id(&self) -> &OsStr {
@@ -658,6 +1325,16 @@ impl std::fmt::Debug for BusInfo {
             s.field("sysfs_path", &self.path);
             s.field("parent_sysfs_path", &self.parent_path);
             s.field("busnum", &self.busnum);
+            s.field(
+                "pci_vendor_id",
+                &self.pci_vendor_id.map(|v| format!("0x{v:04X}")),
+            );
+            s.field(
+                "pci_device_id",
+                &self.pci_device_id.map(|v| format!("0x{v:04X}")),
+            );
+            s.field("num_ports", &self.num_ports);
+            s.field("companion_busnum", &self.companion_busnum);
         }
 
         #[cfg(target_os = "windows")]
@@ -0,0 +1,630 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{DeviceInfo, Speed};
+
+/// Declarative criteria for selecting USB devices.
+///
+/// A `DeviceFilter` matches a [`DeviceInfo`] when all of the criteria that
+/// have been set match. An empty filter matches every device.
+///
+/// Use a `DeviceFilter` with [`list_devices_matching`][`crate::list_devices_matching`],
+/// [`watch_devices_matching`][`crate::watch_devices_matching`], and
+/// [`wait_for_device`][`crate::wait_for_device`], or test a device against it
+/// with [`matches`][`Self::matches`].
+///
+/// ### Example
+///
+/// Build a filter:
+///
+/// ```
+/// use nusb::{DeviceFilter, Speed};
+/// let filter = DeviceFilter::new()
+///     .vendor_id(0x1209)
+///     .product_id(0x0001)
+///     .serial_number("ABC*")
+///     .speed(Speed::High);
+/// ```
+///
+/// Or parse one from a string, such as a command-line argument:
+///
+/// ```
+/// use nusb::DeviceFilter;
+/// let filter: DeviceFilter = "1209:0001,serial=ABC*,speed=high".parse().unwrap();
+/// assert_eq!(filter, nusb::DeviceFilter::new().vendor_id(0x1209).product_id(0x0001)
+///     .serial_number("ABC*").speed(nusb::Speed::High));
+/// ```
+///
+/// ### String syntax
+///
+/// A filter string is a comma-separated list of `key=value` criteria. IDs and
+/// class codes are hexadecimal, with an optional `0x` prefix. String values
+/// are glob patterns, where `*` matches any sequence of characters and `?`
+/// matches any single character, and cannot contain commas.
+///
+/// | Key | Example | Criterion |
+/// |---|---|---|
+/// | `vid` | `vid=1209` | [`vendor_id`][`Self::vendor_id`] |
+/// | `pid` | `pid=0001` | [`product_id`][`Self::product_id`] |
+/// | `serial` | `serial=ABC*` | [`serial_number`][`Self::serial_number`] |
+/// | `manufacturer` | `manufacturer=Acme*` | [`manufacturer_string`][`Self::manufacturer_string`] |
+/// | `product` | `product=*Widget` | [`product_string`][`Self::product_string`] |
+/// | `class` | `class=ef/02/01` | [`class`][`Self::class`], optionally followed by [`subclass`][`Self::subclass`] and [`protocol`][`Self::protocol`] |
+/// | `interface_class` | `interface_class=03` | [`interface_class`][`Self::interface_class`] |
+/// | `bus` | `bus=001` | [`bus_id`][`Self::bus_id`] |
+/// | `port` | `port=1.4.2` | [`port_chain`][`Self::port_chain`] |
+/// | `speed` | `speed=high` | [`speed`][`Self::speed`] |
+///
+/// An item of the form `VID:PID` is shorthand for `vid=VID,pid=PID`, like the
+/// output of `lsusb`.
+///
+/// ### Deserialization
+///
+/// With the `serde` feature enabled, a `DeviceFilter` can be deserialized
+/// from a filter string, or from a map with the keys `vendor_id`,
+/// `product_id`, `serial_number`, `manufacturer_string`, `product_string`,
+/// `class`, `subclass`, `protocol`, `interface_class`, `bus_id`,
+/// `port_chain` and `speed`. For example in TOML:
+///
+/// ```toml
+/// [device]
+/// vendor_id = 0x1209
+/// product_id = 0x0001
+/// serial_number = "ABC*"
+/// port_chain = [1, 4]
+/// ```
+///
+/// It is serialized as a map with the same keys, omitting the criteria that
+/// are not set, so that every filter round-trips, including string patterns
+/// containing commas and subclass or protocol criteria without a class.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "serde_impl::FilterFields", try_from = "serde_impl::FilterRepr")
+)]
+pub struct DeviceFilter {
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+    serial_number: Option<String>,
+    manufacturer_string: Option<String>,
+    product_string: Option<String>,
+    class: Option<u8>,
+    subclass: Option<u8>,
+    protocol: Option<u8>,
+    interface_class: Option<u8>,
+    bus_id: Option<String>,
+    port_chain: Option<Vec<u8>>,
+    speed: Option<Speed>,
+}
+
+impl DeviceFilter {
+    /// Create an empty filter that matches all devices.
+    pub fn new() -> DeviceFilter {
+        DeviceFilter::default()
+    }
+
+    /// Match devices with the specified `idVendor`.
+    pub fn vendor_id(mut self, vendor_id: u16) -> Self {
+        self.vendor_id = Some(vendor_id);
+        self
+    }
+
+    /// Match devices with the specified `idProduct`.
+    pub fn product_id(mut self, product_id: u16) -> Self {
+        self.product_id = Some(product_id);
+        self
+    }
+
+    /// Match devices with a serial number matching the glob `pattern`.
+    pub fn serial_number(mut self, pattern: impl Into<String>) -> Self {
+        self.serial_number = Some(pattern.into());
+        self
+    }
+
+    /// Match devices with a manufacturer string matching the glob `pattern`.
+    pub fn manufacturer_string(mut self, pattern: impl Into<String>) -> Self {
+        self.manufacturer_string = Some(pattern.into());
+        self
+    }
+
+    /// Match devices with a product string matching the glob `pattern`.
+    pub fn product_string(mut self, pattern: impl Into<String>) -> Self {
+        self.product_string = Some(pattern.into());
+        self
+    }
+
+    /// Match devices with the specified `bDeviceClass`.
+    pub fn class(mut self, class: u8) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    /// Match devices with the specified `bDeviceSubClass`.
+    pub fn subclass(mut self, subclass: u8) -> Self {
+        self.subclass = Some(subclass);
+        self
+    }
+
+    /// Match devices with the specified `bDeviceProtocol`.
+    pub fn protocol(mut self, protocol: u8) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Match devices with at least one interface with the specified
+    /// `bInterfaceClass`.
+    ///
+    /// See the platform notes on [`DeviceInfo::interfaces`] for when
+    /// interface information is available.
+    pub fn interface_class(mut self, class: u8) -> Self {
+        self.interface_class = Some(class);
+        self
+    }
+
+    /// Match devices on the specified bus.
+    ///
+    /// If both this and the device's [`bus_id`][`DeviceInfo::bus_id`] are
+    /// numeric, they are compared as numbers, so `1` matches `001`.
+    pub fn bus_id(mut self, bus_id: impl Into<String>) -> Self {
+        self.bus_id = Some(bus_id.into());
+        self
+    }
+
+    /// Match devices connected at the specified
+    /// [`port_chain`][`DeviceInfo::port_chain`].
+    ///
+    /// This is usually combined with [`bus_id`][`Self::bus_id`] to identify
+    /// a physical port.
+    pub fn port_chain(mut self, port_chain: impl Into<Vec<u8>>) -> Self {
+        self.port_chain = Some(port_chain.into());
+        self
+    }
+
+    /// Match devices connected at the specified speed.
+    pub fn speed(mut self, speed: Speed) -> Self {
+        self.speed = Some(speed);
+        self
+    }
+
+    /// Check whether a device matches all of the criteria of this filter.
+    ///
+    /// String criteria do not match a device if the string is not
+    /// available.
+    pub fn matches(&self, device: &DeviceInfo) -> bool {
+        fn eq<T: PartialEq>(criterion: &Option<T>, value: T) -> bool {
+            criterion.as_ref().map_or(true, |c| *c == value)
+        }
+
+        fn glob(criterion: &Option<String>, value: Option<&str>) -> bool {
+            criterion
+                .as_deref()
+                .map_or(true, |c| value.is_some_and(|v| glob_match(c, v)))
+        }
+
+        eq(&self.vendor_id, device.vendor_id())
+            && eq(&self.product_id, device.product_id())
+            && glob(&self.serial_number, device.serial_number())
+            && glob(&self.manufacturer_string, device.manufacturer_string())
+            && glob(&self.product_string, device.product_string())
+            && eq(&self.class, device.class())
+            && eq(&self.subclass, device.subclass())
+            && eq(&self.protocol, device.protocol())
+            && self
+                .interface_class
+                .map_or(true, |c| device.interfaces().any(|i| i.class() == c))
+            && self
+                .bus_id
+                .as_deref()
+                .map_or(true, |b| bus_id_eq(b, device.bus_id()))
+            && self
+                .port_chain
+                .as_deref()
+                .map_or(true, |p| p == device.port_chain())
+            && self.speed.map_or(true, |s| device.speed() == Some(s))
+    }
+}
+
+fn bus_id_eq(a: &str, b: &str) -> bool {
+    match (a.parse::<u32>(), b.parse::<u32>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Match `text` against a glob `pattern` where `*` matches any sequence of
+/// characters and `?` matches any single character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+
+    // Position of the last `*` in the pattern, and the position in the text
+    // it was tried against, to backtrack to on mismatch.
+    let mut star = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Error returned when parsing a [`DeviceFilter`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFilterError(String);
+
+impl Display for ParseFilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid device filter: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseFilterError {}
+
+fn parse_hex<T: TryFrom<u32>>(key: &str, value: &str) -> Result<T, ParseFilterError> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(|v| T::try_from(v).ok())
+        .ok_or_else(|| ParseFilterError(format!("invalid value {value:?} for `{key}`")))
+}
+
+impl FromStr for DeviceFilter {
+    type Err = ParseFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = DeviceFilter::new();
+
+        for item in s.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let Some((key, value)) = item.split_once('=') else {
+                let Some((vid, pid)) = item.split_once(':') else {
+                    return Err(ParseFilterError(format!(
+                        "expected `key=value`, got {item:?}"
+                    )));
+                };
+                filter.vendor_id = Some(parse_hex("vid", vid.trim())?);
+                filter.product_id = Some(parse_hex("pid", pid.trim())?);
+                continue;
+            };
+
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "vid" => filter.vendor_id = Some(parse_hex(key, value)?),
+                "pid" => filter.product_id = Some(parse_hex(key, value)?),
+                "serial" => filter.serial_number = Some(value.to_owned()),
+                "manufacturer" => filter.manufacturer_string = Some(value.to_owned()),
+                "product" => filter.product_string = Some(value.to_owned()),
+                "class" => {
+                    let mut parts = value.split('/');
+                    filter.class = parts.next().map(|v| parse_hex(key, v)).transpose()?;
+                    filter.subclass = parts.next().map(|v| parse_hex(key, v)).transpose()?;
+                    filter.protocol = parts.next().map(|v| parse_hex(key, v)).transpose()?;
+                    if parts.next().is_some() {
+                        return Err(ParseFilterError(format!(
+                            "invalid value {value:?} for `class`"
+                        )));
+                    }
+                }
+                "interface_class" => filter.interface_class = Some(parse_hex(key, value)?),
+                "bus" => filter.bus_id = Some(value.to_owned()),
+                "port" => {
+                    filter.port_chain = Some(
+                        value
+                            .split('.')
+                            .map(|p| p.parse::<u8>())
+                            .collect::<Result<Vec<_>, _>>()
+                            .map_err(|_| {
+                                ParseFilterError(format!("invalid value {value:?} for `port`"))
+                            })?,
+                    )
+                }
+                "speed" => {
+                    filter.speed = Some(Speed::from_str(value).ok_or_else(|| {
+                        ParseFilterError(format!("invalid value {value:?} for `speed`"))
+                    })?)
+                }
+                _ => return Err(ParseFilterError(format!("unknown key `{key}`"))),
+            }
+        }
+
+        Ok(filter)
+    }
+}
+
+impl Display for DeviceFilter {
+    /// Format the filter in the syntax accepted by [`FromStr`].
+    ///
+    /// Filters built with a subclass or protocol but no class, or with
+    /// string patterns containing commas, have no exact string form and do
+    /// not parse back to the same filter.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut items = Vec::new();
+
+        if let Some(v) = self.vendor_id {
+            items.push(format!("vid={v:04x}"));
+        }
+        if let Some(v) = self.product_id {
+            items.push(format!("pid={v:04x}"));
+        }
+        if let Some(v) = &self.serial_number {
+            items.push(format!("serial={v}"));
+        }
+        if let Some(v) = &self.manufacturer_string {
+            items.push(format!("manufacturer={v}"));
+        }
+        if let Some(v) = &self.product_string {
+            items.push(format!("product={v}"));
+        }
+        match (self.class, self.subclass, self.protocol) {
+            (None, None, None) => {}
+            (class, subclass, protocol) => {
+                // Subclass and protocol are positional, so fill in any gaps
+                // with `*`, which fails to parse back. Only the builder can
+                // produce a filter like that.
+                let fmt = |v: Option<u8>| v.map_or("*".to_owned(), |v| format!("{v:02x}"));
+                let mut s = fmt(class);
+                if subclass.is_some() || protocol.is_some() {
+                    s += &format!("/{}", fmt(subclass));
+                }
+                if protocol.is_some() {
+                    s += &format!("/{}", fmt(protocol));
+                }
+                items.push(format!("class={s}"));
+            }
+        }
+        if let Some(v) = self.interface_class {
+            items.push(format!("interface_class={v:02x}"));
+        }
+        if let Some(v) = &self.bus_id {
+            items.push(format!("bus={v}"));
+        }
+        if let Some(v) = &self.port_chain {
+            let ports: Vec<String> = v.iter().map(|p| p.to_string()).collect();
+            items.push(format!("port={}", ports.join(".")));
+        }
+        if let Some(v) = self.speed {
+            items.push(format!("speed={}", v.as_str()));
+        }
+
+        write!(f, "{}", items.join(","))
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::{DeviceFilter, ParseFilterError};
+    use crate::Speed;
+
+    /// Accepted forms of a serialized `DeviceFilter`.
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum FilterRepr {
+        String(String),
+        Fields(FilterFields),
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub(super) struct FilterFields {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        vendor_id: Option<u16>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        product_id: Option<u16>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        serial_number: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        manufacturer_string: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        product_string: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        class: Option<u8>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        subclass: Option<u8>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        protocol: Option<u8>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interface_class: Option<u8>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        bus_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        port_chain: Option<Vec<u8>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        speed: Option<String>,
+    }
+
+    impl From<DeviceFilter> for FilterFields {
+        fn from(f: DeviceFilter) -> FilterFields {
+            FilterFields {
+                vendor_id: f.vendor_id,
+                product_id: f.product_id,
+                serial_number: f.serial_number,
+                manufacturer_string: f.manufacturer_string,
+                product_string: f.product_string,
+                class: f.class,
+                subclass: f.subclass,
+                protocol: f.protocol,
+                interface_class: f.interface_class,
+                bus_id: f.bus_id,
+                port_chain: f.port_chain,
+                speed: f.speed.map(|s| s.as_str().to_owned()),
+            }
+        }
+    }
+
+    impl TryFrom<FilterRepr> for DeviceFilter {
+        type Error = ParseFilterError;
+
+        fn try_from(repr: FilterRepr) -> Result<Self, Self::Error> {
+            let f = match repr {
+                FilterRepr::String(s) => return s.parse(),
+                FilterRepr::Fields(f) => f,
+            };
+
+            let speed = f
+                .speed
+                .map(|s| {
+                    Speed::from_str(&s)
+                        .ok_or_else(|| ParseFilterError(format!("invalid speed {s:?}")))
+                })
+                .transpose()?;
+
+            Ok(DeviceFilter {
+                vendor_id: f.vendor_id,
+                product_id: f.product_id,
+                serial_number: f.serial_number,
+                manufacturer_string: f.manufacturer_string,
+                product_string: f.product_string,
+                class: f.class,
+                subclass: f.subclass,
+                protocol: f.protocol,
+                interface_class: f.interface_class,
+                bus_id: f.bus_id,
+                port_chain: f.port_chain,
+                speed,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, DeviceFilter};
+    use crate::Speed;
+
+    #[test]
+    fn test_glob() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+        assert!(glob_match("abc", "abc"));
+        assert!(!glob_match("abc", "abd"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("AB*", "AB1234"));
+        assert!(!glob_match("AB*", "A1234"));
+        assert!(glob_match("*34", "AB1234"));
+        assert!(glob_match("A*2*4", "AB1234"));
+        assert!(!glob_match("A*2*5", "AB1234"));
+        assert!(glob_match("*a*a*", "banana"));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("".parse::<DeviceFilter>().unwrap(), DeviceFilter::new());
+
+        assert_eq!(
+            "1209:0x000a".parse::<DeviceFilter>().unwrap(),
+            DeviceFilter::new().vendor_id(0x1209).product_id(0x000a)
+        );
+
+        assert_eq!(
+            "vid=1209, serial=AB*, product=My Device, class=ef/2/1, interface_class=3, bus=1, port=1.4, speed=super+"
+                .parse::<DeviceFilter>()
+                .unwrap(),
+            DeviceFilter::new()
+                .vendor_id(0x1209)
+                .serial_number("AB*")
+                .product_string("My Device")
+                .class(0xef)
+                .subclass(0x02)
+                .protocol(0x01)
+                .interface_class(0x03)
+                .bus_id("1")
+                .port_chain([1, 4])
+                .speed(Speed::SuperPlus)
+        );
+
+        assert!("vid=12345".parse::<DeviceFilter>().is_err());
+        assert!("vid".parse::<DeviceFilter>().is_err());
+        assert!("color=red".parse::<DeviceFilter>().is_err());
+        assert!("port=1.x".parse::<DeviceFilter>().is_err());
+        assert!("speed=ludicrous".parse::<DeviceFilter>().is_err());
+        assert!("class=1/2/3/4".parse::<DeviceFilter>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let filter = DeviceFilter::new()
+            .vendor_id(0x1209)
+            .product_id(0x0001)
+            .manufacturer_string("Acme*")
+            .class(0xff)
+            .subclass(0x01)
+            .bus_id("003")
+            .port_chain([2, 1])
+            .speed(Speed::High);
+        let s = filter.to_string();
+        assert_eq!(
+            s,
+            "vid=1209,pid=0001,manufacturer=Acme*,class=ff/01,bus=003,port=2.1,speed=high"
+        );
+        assert_eq!(s.parse::<DeviceFilter>().unwrap(), filter);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize() {
+        let from_map: DeviceFilter = serde_json::from_str(
+            r#"{ "vendor_id": 4617, "serial_number": "AB*", "port_chain": [1, 4], "speed": "high" }"#,
+        )
+        .unwrap();
+        let expected = DeviceFilter::new()
+            .vendor_id(0x1209)
+            .serial_number("AB*")
+            .port_chain([1, 4])
+            .speed(Speed::High);
+        assert_eq!(from_map, expected);
+
+        let from_str: DeviceFilter =
+            serde_json::from_str(r#""vid=1209,serial=AB*,port=1.4,speed=high""#).unwrap();
+        assert_eq!(from_str, expected);
+
+        assert!(serde_json::from_str::<DeviceFilter>(r#"{ "color": "red" }"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let filter = DeviceFilter::new()
+            .vendor_id(0x1209)
+            .serial_number("A,B*")
+            .subclass(0x02)
+            .port_chain([1, 4])
+            .speed(Speed::High);
+        let json = serde_json::to_string(&filter).unwrap();
+        assert_eq!(
+            json,
+            r#"{"vendor_id":4617,"serial_number":"A,B*","subclass":2,"port_chain":[1,4],"speed":"high"}"#
+        );
+        assert_eq!(serde_json::from_str::<DeviceFilter>(&json).unwrap(), filter);
+
+        let empty = serde_json::to_string(&DeviceFilter::new()).unwrap();
+        assert_eq!(empty, "{}");
+        assert_eq!(
+            serde_json::from_str::<DeviceFilter>(&empty).unwrap(),
+            DeviceFilter::new()
+        );
+    }
+}
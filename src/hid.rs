@@ -0,0 +1,195 @@
+//! Human Interface Device (HID) class support.
+//!
+//! HID interfaces (interface class 3) are followed by a [`HidDescriptor`] in
+//! their configuration descriptor, and describe the format of their reports
+//! with a report descriptor that is parsed by [`ReportDescriptor::parse`].
+//!
+//! The class-specific requests are available as methods on
+//! [`Interface`][crate::Interface]:
+//! [`hid_get_report_descriptor`][crate::Interface::hid_get_report_descriptor],
+//! [`hid_get_report`][crate::Interface::hid_get_report],
+//! [`hid_set_report`][crate::Interface::hid_set_report],
+//! [`hid_set_idle`][crate::Interface::hid_set_idle], and
+//! [`hid_set_protocol`][crate::Interface::hid_set_protocol].
+//!
+//! ### Example
+//!
+//! ```no_run
+//! use futures_lite::future::block_on;
+//! use nusb::hid::{HidDescriptor, ReportDescriptor};
+//! # fn main() -> Result<(), std::io::Error> {
+//! # let di = nusb::list_devices().unwrap().next().unwrap();
+//! # let device = di.open().unwrap();
+//! let interface = device.detach_and_claim_interface(0)?;
+//!
+//! let alt = interface.descriptors().next().unwrap();
+//! let hid = HidDescriptor::from_interface(&alt).expect("not a HID interface");
+//! let len = hid.report_descriptor_length().unwrap_or(4096);
+//!
+//! let buf = block_on(interface.hid_get_report_descriptor(len)).into_result()?;
+//! let report_descriptor = ReportDescriptor::parse(&buf)?;
+//!
+//! for report in report_descriptor.reports() {
+//!     println!("{:?} report {:?}: {} bytes", report.report_type(), report.report_id(), report.size());
+//! }
+//! # Ok(()) }
+//! ```
+
+use std::fmt::Debug;
+
+use crate::descriptors::{Descriptor, InterfaceAltSetting};
+
+mod report;
+pub use report::{
+    Collection, CollectionKind, Field, ParseReportDescriptorError, Report, ReportDescriptor,
+    ReportValue, Usage,
+};
+
+mod device;
+pub use device::{HidDevice, InputReport};
+
+pub(crate) const DESCRIPTOR_TYPE_HID: u8 = 0x21;
+pub(crate) const DESCRIPTOR_TYPE_REPORT: u8 = 0x22;
+const DESCRIPTOR_LEN_HID: u8 = 6;
+
+pub(crate) const REQUEST_GET_REPORT: u8 = 0x01;
+pub(crate) const REQUEST_SET_REPORT: u8 = 0x09;
+pub(crate) const REQUEST_SET_IDLE: u8 = 0x0A;
+pub(crate) const REQUEST_SET_PROTOCOL: u8 = 0x0B;
+
+/// Type of a HID report.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ReportType {
+    /// Report sent from the device to the host.
+    Input = 1,
+
+    /// Report sent from the host to the device.
+    Output = 2,
+
+    /// Configuration report, read and written with control requests.
+    Feature = 3,
+}
+
+/// Protocol selected with [`Interface::hid_set_protocol`][crate::Interface::hid_set_protocol].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Protocol {
+    /// The fixed report format defined by the HID specification for boot
+    /// keyboards and mice.
+    Boot = 0,
+
+    /// The report format described by the report descriptor.
+    Report = 1,
+}
+
+/// HID class descriptor, which follows the interface descriptor of a HID
+/// interface and lists the lengths of its report and physical descriptors.
+#[derive(Clone)]
+pub struct HidDescriptor<'a>(&'a [u8]);
+
+impl<'a> HidDescriptor<'a> {
+    /// Create a `HidDescriptor` from a buffer containing the descriptor.
+    ///
+    /// Returns `None` if the buffer is not a HID descriptor, or is too short
+    /// for the number of class descriptors it declares.
+    pub fn new(buf: &[u8]) -> Option<HidDescriptor<'_>> {
+        let buf = buf.get(..*buf.first()? as usize)?;
+        if buf.len() < DESCRIPTOR_LEN_HID as usize
+            || buf[1] != DESCRIPTOR_TYPE_HID
+            || buf.len() < DESCRIPTOR_LEN_HID as usize + 3 * buf[5] as usize
+        {
+            return None;
+        }
+        Some(HidDescriptor(buf))
+    }
+
+    /// Find the HID descriptor of an interface alternate setting.
+    ///
+    /// Returns `None` if the interface has no valid HID descriptor.
+    pub fn from_interface(intf: &'a InterfaceAltSetting<'_>) -> Option<HidDescriptor<'a>> {
+        let mut buf = intf.descriptors().as_bytes();
+        while let Some(desc) = Descriptor::new(buf) {
+            let len = desc.descriptor_len();
+            if len < 2 {
+                return None;
+            }
+            if desc.descriptor_type() == DESCRIPTOR_TYPE_HID {
+                return HidDescriptor::new(buf);
+            }
+            buf = &buf[len..];
+        }
+        None
+    }
+
+    /// `bcdHID` descriptor field: HID specification version, in binary-coded decimal.
+    #[doc(alias = "bcdHID")]
+    pub fn hid_version(&self) -> u16 {
+        u16::from_le_bytes([self.0[2], self.0[3]])
+    }
+
+    /// `bCountryCode` descriptor field: Country code of localized hardware,
+    /// or `0` if not localized.
+    #[doc(alias = "bCountryCode")]
+    pub fn country_code(&self) -> u8 {
+        self.0[4]
+    }
+
+    /// `bNumDescriptors` descriptor field: Number of class descriptors.
+    #[doc(alias = "bNumDescriptors")]
+    pub fn num_descriptors(&self) -> u8 {
+        self.0[5]
+    }
+
+    /// Iterator over the `bDescriptorType` and `wDescriptorLength` fields of
+    /// the class descriptors.
+    pub fn class_descriptors(&self) -> impl Iterator<Item = (u8, u16)> + 'a {
+        self.0[DESCRIPTOR_LEN_HID as usize..]
+            .chunks_exact(3)
+            .take(self.num_descriptors() as usize)
+            .map(|c| (c[0], u16::from_le_bytes([c[1], c[2]])))
+    }
+
+    /// Length of the report descriptor.
+    pub fn report_descriptor_length(&self) -> Option<u16> {
+        self.class_descriptors()
+            .find(|&(t, _)| t == DESCRIPTOR_TYPE_REPORT)
+            .map(|(_, len)| len)
+    }
+}
+
+impl<'a> Debug for HidDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HidDescriptor")
+            .field("hid_version", &format_args!("0x{:04X}", self.hid_version()))
+            .field("country_code", &self.country_code())
+            .field(
+                "class_descriptors",
+                &self.class_descriptors().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_hid_descriptor() {
+    use crate::descriptors::Configuration;
+
+    let buf = [
+        0x09, 0x02, 0x22, 0x00, 0x01, 0x01, 0x00, 0xa0, 0x32,
+        0x09, 0x04, 0x00, 0x00, 0x01, 0x03, 0x01, 0x01, 0x00,
+        0x09, 0x21, 0x11, 0x01, 0x00, 0x01, 0x22, 0x3f, 0x00,
+        0x07, 0x05, 0x81, 0x03, 0x08, 0x00, 0x0a,
+    ];
+    let config = Configuration::new(&buf);
+    let alt = config.interface_alt_settings().next().unwrap();
+
+    let hid = HidDescriptor::from_interface(&alt).unwrap();
+    assert_eq!(hid.hid_version(), 0x0111);
+    assert_eq!(hid.country_code(), 0);
+    assert_eq!(hid.class_descriptors().collect::<Vec<_>>(), [(0x22, 0x3f)]);
+    assert_eq!(hid.report_descriptor_length(), Some(0x3f));
+
+    assert!(HidDescriptor::new(&[0x09, 0x21, 0x11, 0x01, 0x00, 0x02, 0x22, 0x3f, 0x00]).is_none());
+}
@@ -0,0 +1,367 @@
+//! HID device wrapper using the interrupt endpoints.
+
+use std::{
+    fmt::Debug,
+    future::{poll_fn, Future},
+    io::ErrorKind,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use super::{ReportDescriptor, ReportType, ReportValue, DESCRIPTOR_TYPE_REPORT};
+use crate::{
+    device::STANDARD_REQUEST_GET_DESCRIPTOR,
+    transfer::{
+        Control, ControlOut, ControlType, Direction, EndpointType, Queue, Recipient, RequestBuffer,
+        TransferError, TransferFuture,
+    },
+    Error, Interface,
+};
+
+/// Number of transfers kept pending on the interrupt IN endpoint.
+const NUM_TRANSFERS: usize = 4;
+
+/// Largest report descriptor read by [`HidDevice::open`].
+const MAX_REPORT_DESCRIPTOR_LEN: usize = 4096;
+
+/// Length requested by [`HidDevice::get_feature_report`] for a report that
+/// isn't in the report descriptor.
+const UNKNOWN_FEATURE_REPORT_LEN: usize = 4096;
+
+/// A HID device, reading input reports from its interrupt IN endpoint and
+/// writing output reports to its interrupt OUT endpoint.
+///
+/// This provides an API similar to hidapi for a HID interface that has been
+/// detached from the kernel driver. The interrupt IN endpoint is kept polled
+/// with several pending transfers, so that input reports are not missed
+/// between calls to [`read`][Self::read].
+///
+/// ### Example
+///
+/// ```no_run
+/// use futures_lite::future::block_on;
+/// use nusb::hid::HidDevice;
+/// use std::time::Duration;
+/// # fn main() -> Result<(), std::io::Error> {
+/// # let di = nusb::list_devices().unwrap().next().unwrap();
+/// # let device = di.open().unwrap();
+/// let interface = device.detach_and_claim_interface(0)?;
+/// let mut hid = HidDevice::open(interface, Duration::from_secs(1))?;
+///
+/// loop {
+///     let report = block_on(hid.read())?;
+///     for value in hid.decode(&report) {
+///         println!("{:?} = {}", value.usage, value.value);
+///     }
+/// }
+/// # }
+/// ```
+pub struct HidDevice {
+    interface: Interface,
+    report_descriptor: ReportDescriptor,
+    in_queue: Queue<RequestBuffer>,
+    in_transfer_len: usize,
+    out_endpoint: Option<u8>,
+}
+
+impl HidDevice {
+    /// Read the report descriptor of a claimed HID interface and start
+    /// reading input reports.
+    ///
+    /// The report descriptor is read with a blocking control transfer that
+    /// fails after `timeout`.
+    pub fn open(interface: Interface, timeout: Duration) -> Result<HidDevice, Error> {
+        let mut buf = vec![0; MAX_REPORT_DESCRIPTOR_LEN];
+        let len = interface.control_in_blocking(
+            Control {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Interface,
+                request: STANDARD_REQUEST_GET_DESCRIPTOR,
+                value: (DESCRIPTOR_TYPE_REPORT as u16) << 8,
+                index: interface.interface_number() as u16,
+            },
+            &mut buf,
+            timeout,
+        )?;
+
+        let report_descriptor = ReportDescriptor::parse(&buf[..len])?;
+        HidDevice::new(interface, report_descriptor)
+    }
+
+    /// Start reading input reports from a claimed HID interface, using a
+    /// report descriptor that was already read and parsed.
+    ///
+    /// Returns an error if the interface does not have an interrupt IN
+    /// endpoint.
+    pub fn new(
+        interface: Interface,
+        report_descriptor: ReportDescriptor,
+    ) -> Result<HidDevice, Error> {
+        let alt = interface
+            .descriptors()
+            .find(|alt| alt.alternate_setting() == 0)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "interface descriptor not found"))?;
+
+        let interrupt_endpoint = |direction| {
+            alt.endpoints().find(|ep| {
+                ep.transfer_type() == EndpointType::Interrupt && ep.direction() == direction
+            })
+        };
+
+        let in_endpoint = interrupt_endpoint(Direction::In)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no interrupt IN endpoint"))?;
+        let out_endpoint = interrupt_endpoint(Direction::Out).map(|ep| ep.address());
+
+        // Request whole packets, and enough of them for the largest input
+        // report and its report ID byte.
+        let packet_size = in_endpoint.max_packet_size().max(1);
+        let report_len = report_descriptor
+            .reports()
+            .filter(|r| r.report_type() == ReportType::Input)
+            .map(|r| r.size() + r.report_id().is_some() as usize)
+            .max()
+            .unwrap_or(0);
+        let in_transfer_len = report_len.max(1).div_ceil(packet_size) * packet_size;
+
+        let mut in_queue = interface.interrupt_in_queue(in_endpoint.address());
+        for _ in 0..NUM_TRANSFERS {
+            in_queue.submit(RequestBuffer::new(in_transfer_len));
+        }
+
+        Ok(HidDevice {
+            interface,
+            report_descriptor,
+            in_queue,
+            in_transfer_len,
+            out_endpoint,
+        })
+    }
+
+    /// The claimed interface.
+    pub fn interface(&self) -> &Interface {
+        &self.interface
+    }
+
+    /// The parsed report descriptor.
+    pub fn report_descriptor(&self) -> &ReportDescriptor {
+        &self.report_descriptor
+    }
+
+    /// Return a `Future` that waits for the next input report.
+    ///
+    /// This future is cancel-safe: reports that arrive after it is dropped
+    /// are returned by the next call.
+    pub fn read(
+        &mut self,
+    ) -> impl Future<Output = Result<InputReport, TransferError>> + Unpin + Send + Sync + '_ {
+        poll_fn(|cx| self.poll_read(cx))
+    }
+
+    /// Get the next input report if one has been received, or register the
+    /// current task for wakeup when the next transfer completes.
+    ///
+    /// Each completed transfer is one report. Zero-length transfers are
+    /// skipped.
+    pub fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<Result<InputReport, TransferError>> {
+        loop {
+            if self.in_queue.pending() == 0 {
+                return Poll::Ready(Err(TransferError::Disconnected));
+            }
+
+            let completion = std::task::ready!(self.in_queue.poll_next(cx));
+            let status = completion.status;
+            let report = status
+                .is_ok()
+                .then(|| {
+                    InputReport::from_transfer(
+                        self.report_descriptor.uses_report_ids(),
+                        &completion.data,
+                    )
+                })
+                .flatten();
+
+            // Keep the endpoint polled, unless the device is gone.
+            if !matches!(status, Err(TransferError::Disconnected)) {
+                self.in_queue
+                    .submit(RequestBuffer::reuse(completion.data, self.in_transfer_len));
+            }
+
+            status?;
+            if let Some(report) = report {
+                return Poll::Ready(Ok(report));
+            }
+        }
+    }
+
+    /// Decode an input report against the report descriptor.
+    ///
+    /// Returns an empty `Vec` if the report ID is not described by the
+    /// report descriptor. See [`Report::decode`][super::Report::decode].
+    pub fn decode(&self, report: &InputReport) -> Vec<ReportValue> {
+        self.report_descriptor
+            .report(ReportType::Input, report.report_id)
+            .map(|r| r.decode(&report.data))
+            .unwrap_or_default()
+    }
+
+    /// Send an output report.
+    ///
+    /// `data` does not include the report ID; pass a `report_id` of `0` if
+    /// the device does not use report IDs. The report is sent on the
+    /// interrupt OUT endpoint if the interface has one, or with a
+    /// `SET_REPORT` request otherwise.
+    pub fn write(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> impl Future<Output = Result<(), TransferError>> + Send + Sync {
+        let buf = with_report_id(report_id, data);
+
+        let t = match self.out_endpoint {
+            Some(ep) => WriteTransfer::Interrupt(self.interface.interrupt_out(ep, buf)),
+            None => WriteTransfer::Control(self.interface.hid_set_report(
+                ReportType::Output,
+                report_id,
+                &buf,
+            )),
+        };
+
+        async move {
+            match t {
+                WriteTransfer::Interrupt(t) => t.await.status,
+                WriteTransfer::Control(t) => t.await.status,
+            }
+        }
+    }
+
+    /// Read a feature report with a `GET_REPORT` request.
+    ///
+    /// The returned data does not include the report ID; pass a `report_id`
+    /// of `0` if the device does not use report IDs.
+    pub fn get_feature_report(
+        &self,
+        report_id: u8,
+    ) -> impl Future<Output = Result<Vec<u8>, TransferError>> + Send + Sync {
+        let id = Some(report_id).filter(|&id| id != 0);
+        let len = self
+            .report_descriptor
+            .report(ReportType::Feature, id)
+            .map_or(UNKNOWN_FEATURE_REPORT_LEN, |r| r.size())
+            + id.is_some() as usize;
+        let len = u16::try_from(len).unwrap_or(u16::MAX);
+
+        let t = self
+            .interface
+            .hid_get_report(ReportType::Feature, report_id, len);
+
+        async move {
+            let mut data = t.await.into_result()?;
+            if id.is_some() && !data.is_empty() {
+                data.remove(0);
+            }
+            Ok(data)
+        }
+    }
+
+    /// Send a feature report with a `SET_REPORT` request.
+    ///
+    /// `data` does not include the report ID; pass a `report_id` of `0` if
+    /// the device does not use report IDs.
+    pub fn send_feature_report(
+        &self,
+        report_id: u8,
+        data: &[u8],
+    ) -> impl Future<Output = Result<(), TransferError>> + Send + Sync {
+        let t = self.interface.hid_set_report(
+            ReportType::Feature,
+            report_id,
+            &with_report_id(report_id, data),
+        );
+        async move { t.await.status }
+    }
+}
+
+/// The transfer used by [`HidDevice::write`].
+enum WriteTransfer {
+    Interrupt(TransferFuture<Vec<u8>>),
+    Control(TransferFuture<ControlOut<'static>>),
+}
+
+impl Debug for HidDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HidDevice")
+            .field("interface_number", &self.interface.interface_number())
+            .field("out_endpoint", &self.out_endpoint)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Prefix `data` with the report ID byte, unless the ID is `0`.
+fn with_report_id(report_id: u8, data: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(data.len() + 1);
+    if report_id != 0 {
+        buf.push(report_id);
+    }
+    buf.extend_from_slice(data);
+    buf
+}
+
+/// An input report received by [`HidDevice::read`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputReport {
+    report_id: Option<u8>,
+    data: Vec<u8>,
+}
+
+impl InputReport {
+    /// Parse the data of an interrupt IN transfer, which is a single report
+    /// starting with the report ID byte if the device uses report IDs.
+    ///
+    /// Returns `None` for a zero-length transfer.
+    fn from_transfer(uses_report_ids: bool, data: &[u8]) -> Option<InputReport> {
+        let (report_id, data) = match data {
+            [] => return None,
+            [id, data @ ..] if uses_report_ids => (Some(*id), data),
+            data => (None, data),
+        };
+        Some(InputReport {
+            report_id,
+            data: data.to_vec(),
+        })
+    }
+
+    /// Report ID, or `None` if the device does not use report IDs.
+    pub fn report_id(&self) -> Option<u8> {
+        self.report_id
+    }
+
+    /// Report data, not including the report ID byte.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take the report data, not including the report ID byte.
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+#[test]
+fn test_input_report_from_transfer() {
+    // The whole transfer is one report, including any padding after the
+    // report's declared size.
+    let report = InputReport::from_transfer(true, &[0x01, 0xaa, 0xbb, 0x00, 0x00]).unwrap();
+    assert_eq!(report.report_id(), Some(1));
+    assert_eq!(report.data(), [0xaa, 0xbb, 0x00, 0x00]);
+
+    let report = InputReport::from_transfer(false, &[0x01, 0xaa]).unwrap();
+    assert_eq!(report.report_id(), None);
+    assert_eq!(report.into_data(), [0x01, 0xaa]);
+
+    let report = InputReport::from_transfer(true, &[0x02]).unwrap();
+    assert_eq!(report.report_id(), Some(2));
+    assert!(report.data().is_empty());
+
+    assert_eq!(InputReport::from_transfer(true, &[]), None);
+    assert_eq!(InputReport::from_transfer(false, &[]), None);
+}
@@ -0,0 +1,925 @@
+//! Parser for HID report descriptors.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display},
+    io::ErrorKind,
+};
+
+use super::ReportType;
+use crate::Error;
+
+const ITEM_TYPE_MAIN: u8 = 0;
+const ITEM_TYPE_GLOBAL: u8 = 1;
+const ITEM_TYPE_LOCAL: u8 = 2;
+const ITEM_LONG: u8 = 0xFE;
+
+const MAIN_INPUT: u8 = 0x8;
+const MAIN_OUTPUT: u8 = 0x9;
+const MAIN_COLLECTION: u8 = 0xA;
+const MAIN_FEATURE: u8 = 0xB;
+const MAIN_END_COLLECTION: u8 = 0xC;
+
+const GLOBAL_USAGE_PAGE: u8 = 0x0;
+const GLOBAL_LOGICAL_MINIMUM: u8 = 0x1;
+const GLOBAL_LOGICAL_MAXIMUM: u8 = 0x2;
+const GLOBAL_PHYSICAL_MINIMUM: u8 = 0x3;
+const GLOBAL_PHYSICAL_MAXIMUM: u8 = 0x4;
+const GLOBAL_UNIT_EXPONENT: u8 = 0x5;
+const GLOBAL_UNIT: u8 = 0x6;
+const GLOBAL_REPORT_SIZE: u8 = 0x7;
+const GLOBAL_REPORT_ID: u8 = 0x8;
+const GLOBAL_REPORT_COUNT: u8 = 0x9;
+const GLOBAL_PUSH: u8 = 0xA;
+const GLOBAL_POP: u8 = 0xB;
+
+const LOCAL_USAGE: u8 = 0x0;
+const LOCAL_USAGE_MINIMUM: u8 = 0x1;
+const LOCAL_USAGE_MAXIMUM: u8 = 0x2;
+
+/// Largest supported `Report Size`, so that every value fits in an `i32`.
+const MAX_REPORT_SIZE: u32 = 32;
+
+/// Largest supported report, in bits.
+const MAX_REPORT_BITS: u32 = 0xFFFF * 8;
+
+/// Largest supported `Usage Minimum` to `Usage Maximum` range.
+const MAX_USAGE_RANGE: u32 = 0xFFFF;
+
+/// A HID usage, identifying what a field or collection represents.
+///
+/// Usages are defined in the [HID Usage Tables], and consist of a 16-bit
+/// usage page and a 16-bit usage ID.
+///
+/// [HID Usage Tables]: https://usb.org/document-library/hid-usage-tables-15
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Usage(u32);
+
+impl Usage {
+    /// Create a `Usage` from a usage page and usage ID.
+    pub const fn new(page: u16, id: u16) -> Usage {
+        Usage(((page as u32) << 16) | id as u32)
+    }
+
+    /// Create a `Usage` from an extended 32-bit usage, with the usage page
+    /// in the high 16 bits.
+    pub const fn from_extended(usage: u32) -> Usage {
+        Usage(usage)
+    }
+
+    /// The usage page.
+    pub fn page(&self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+
+    /// The usage ID within the usage page.
+    pub fn id(&self) -> u16 {
+        self.0 as u16
+    }
+
+    /// The extended 32-bit usage, with the usage page in the high 16 bits.
+    pub fn extended(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Debug for Usage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Usage(0x{:04X}:0x{:04X})", self.page(), self.id())
+    }
+}
+
+/// Type of a [`Collection`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CollectionKind {
+    /// A group of axes measured at one geometric point.
+    Physical,
+
+    /// A group of items with a common purpose, such as a keyboard or mouse.
+    Application,
+
+    /// A group of items that form a composite data structure.
+    Logical,
+
+    /// The items of a report.
+    Report,
+
+    /// An array of selector usages.
+    NamedArray,
+
+    /// A usage that modifies the meaning of the usages it contains.
+    UsageSwitch,
+
+    /// A usage that modifies the usages it contains.
+    UsageModifier,
+
+    /// A reserved or vendor-defined collection type.
+    Other(u8),
+}
+
+impl CollectionKind {
+    fn from_u8(value: u8) -> CollectionKind {
+        match value {
+            0x00 => CollectionKind::Physical,
+            0x01 => CollectionKind::Application,
+            0x02 => CollectionKind::Logical,
+            0x03 => CollectionKind::Report,
+            0x04 => CollectionKind::NamedArray,
+            0x05 => CollectionKind::UsageSwitch,
+            0x06 => CollectionKind::UsageModifier,
+            other => CollectionKind::Other(other),
+        }
+    }
+}
+
+/// A collection in a report descriptor, which groups related fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collection {
+    kind: CollectionKind,
+    usage: Option<Usage>,
+    parent: Option<usize>,
+}
+
+impl Collection {
+    /// Type of the collection.
+    pub fn kind(&self) -> CollectionKind {
+        self.kind
+    }
+
+    /// Usage of the collection, such as Generic Desktop / Mouse for the
+    /// application collection of a mouse.
+    pub fn usage(&self) -> Option<Usage> {
+        self.usage
+    }
+
+    /// Index in [`ReportDescriptor::collections`] of the enclosing
+    /// collection, or `None` for a top-level collection.
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+}
+
+/// A field of a report, from an Input, Output, or Feature main item.
+///
+/// A field contains [`count`][Self::count] values of
+/// [`bit_size`][Self::bit_size] bits each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    bit_offset: u32,
+    bit_size: u32,
+    count: u32,
+    flags: u32,
+    usages: Vec<Usage>,
+    logical_minimum: i32,
+    logical_maximum: i32,
+    physical_minimum: i32,
+    physical_maximum: i32,
+    unit: u32,
+    unit_exponent: i8,
+    collection: Option<usize>,
+}
+
+impl Field {
+    /// Offset of the first value in bits from the start of the report data,
+    /// not including the report ID byte.
+    pub fn bit_offset(&self) -> u32 {
+        self.bit_offset
+    }
+
+    /// `Report Size` item: size of each value in bits.
+    pub fn bit_size(&self) -> u32 {
+        self.bit_size
+    }
+
+    /// `Report Count` item: number of values.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Raw data bits of the main item.
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Whether the field is constant, usually padding.
+    pub fn is_constant(&self) -> bool {
+        self.flags & (1 << 0) != 0
+    }
+
+    /// Whether each value is a variable for one usage, rather than an
+    /// array of indexes of the usages that are active.
+    pub fn is_variable(&self) -> bool {
+        self.flags & (1 << 1) != 0
+    }
+
+    /// Whether values are relative to the previous report, rather than
+    /// absolute.
+    pub fn is_relative(&self) -> bool {
+        self.flags & (1 << 2) != 0
+    }
+
+    /// Whether values wrap around from the maximum to the minimum.
+    pub fn wraps(&self) -> bool {
+        self.flags & (1 << 3) != 0
+    }
+
+    /// Whether the values have a non-linear relationship to the measured
+    /// quantity.
+    pub fn is_nonlinear(&self) -> bool {
+        self.flags & (1 << 4) != 0
+    }
+
+    /// Whether the control has no preferred state to return to when not
+    /// being interacted with.
+    pub fn has_no_preferred_state(&self) -> bool {
+        self.flags & (1 << 5) != 0
+    }
+
+    /// Whether values outside the logical range indicate that there is no
+    /// meaningful data.
+    pub fn has_null_state(&self) -> bool {
+        self.flags & (1 << 6) != 0
+    }
+
+    /// Whether the field is a stream of bytes rather than separate values.
+    pub fn is_buffered_bytes(&self) -> bool {
+        self.flags & (1 << 8) != 0
+    }
+
+    /// Usages of the field.
+    ///
+    /// For a variable field, this is the usage of each value in order, with
+    /// the last usage repeated for any remaining values. For an array field,
+    /// this is the list of usages that values index, starting at the
+    /// logical minimum.
+    pub fn usages(&self) -> &[Usage] {
+        &self.usages
+    }
+
+    /// Usage of the value at `index` of a variable field.
+    pub fn usage(&self, index: u32) -> Option<Usage> {
+        let index = (index as usize).min(self.usages.len().checked_sub(1)?);
+        self.usages.get(index).copied()
+    }
+
+    /// Extract the value at `index` from report data that does not include
+    /// the report ID byte.
+    ///
+    /// Values are sign-extended if the logical minimum is negative. Returns
+    /// `None` if `index` is out of range or `data` is too short.
+    pub fn value(&self, data: &[u8], index: u32) -> Option<i32> {
+        if index >= self.count {
+            return None;
+        }
+
+        let offset = self.bit_offset as usize + (index * self.bit_size) as usize;
+        let end = offset + self.bit_size as usize;
+        if end > data.len() * 8 {
+            return None;
+        }
+
+        let mut raw = 0u64;
+        for (i, b) in data[offset / 8..end.div_ceil(8)].iter().enumerate() {
+            raw |= (*b as u64) << (8 * i);
+        }
+        let raw = ((raw >> (offset % 8)) & ((1 << self.bit_size) - 1)) as u32;
+
+        let shift = 32 - self.bit_size;
+        if self.logical_minimum < 0 && shift > 0 {
+            Some(((raw << shift) as i32) >> shift)
+        } else {
+            Some(raw as i32)
+        }
+    }
+
+    /// `Logical Minimum` item: smallest value.
+    pub fn logical_minimum(&self) -> i32 {
+        self.logical_minimum
+    }
+
+    /// `Logical Maximum` item: largest value.
+    pub fn logical_maximum(&self) -> i32 {
+        self.logical_maximum
+    }
+
+    /// `Physical Minimum` item: physical quantity corresponding to the
+    /// logical minimum, in [`unit`][Self::unit]s.
+    ///
+    /// If both physical limits are zero, the logical limits apply.
+    pub fn physical_minimum(&self) -> i32 {
+        self.physical_minimum
+    }
+
+    /// `Physical Maximum` item: physical quantity corresponding to the
+    /// logical maximum, in [`unit`][Self::unit]s.
+    pub fn physical_maximum(&self) -> i32 {
+        self.physical_maximum
+    }
+
+    /// `Unit` item: the system of measurement and exponents of its base
+    /// units, as defined in section 6.2.2.7 of the HID specification.
+    pub fn unit(&self) -> u32 {
+        self.unit
+    }
+
+    /// `Unit Exponent` item: power of ten applied to physical values.
+    pub fn unit_exponent(&self) -> i8 {
+        self.unit_exponent
+    }
+
+    /// Index in [`ReportDescriptor::collections`] of the innermost
+    /// collection containing the field.
+    pub fn collection(&self) -> Option<usize> {
+        self.collection
+    }
+}
+
+/// The fields of an input, output, or feature report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    report_type: ReportType,
+    report_id: Option<u8>,
+    bit_len: u32,
+    fields: Vec<Field>,
+}
+
+impl Report {
+    /// Type of the report.
+    pub fn report_type(&self) -> ReportType {
+        self.report_type
+    }
+
+    /// Report ID, or `None` if the device does not use report IDs.
+    ///
+    /// When report IDs are used, reports are prefixed with a report ID byte
+    /// that is not included in the field offsets.
+    pub fn report_id(&self) -> Option<u8> {
+        self.report_id
+    }
+
+    /// Size of the report data in bytes, not including the report ID byte.
+    pub fn size(&self) -> usize {
+        self.bit_len.div_ceil(8) as usize
+    }
+
+    /// Fields of the report, in order.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Decode report data that does not include the report ID byte into the
+    /// values of its usages.
+    ///
+    /// Each value of a variable field is returned with its usage. For an
+    /// array field, each usage that is active is returned with a value of
+    /// `1`. Constant fields are skipped, as are values that are beyond the
+    /// end of `data`.
+    pub fn decode(&self, data: &[u8]) -> Vec<ReportValue> {
+        let mut values = Vec::new();
+
+        for field in self.fields.iter().filter(|f| !f.is_constant()) {
+            for index in 0..field.count {
+                let Some(value) = field.value(data, index) else {
+                    break;
+                };
+
+                if field.is_variable() {
+                    if let Some(usage) = field.usage(index) {
+                        values.push(ReportValue { usage, value });
+                    }
+                } else {
+                    // Usage ID 0 is reserved, and used by arrays to mean
+                    // that no control is active.
+                    let usage = usize::try_from(value.wrapping_sub(field.logical_minimum))
+                        .ok()
+                        .filter(|_| value <= field.logical_maximum)
+                        .and_then(|i| field.usages.get(i))
+                        .filter(|u| u.id() != 0);
+                    if let Some(&usage) = usage {
+                        values.push(ReportValue { usage, value: 1 });
+                    }
+                }
+            }
+        }
+
+        values
+    }
+}
+
+/// A value decoded by [`Report::decode`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReportValue {
+    /// Usage of the value.
+    pub usage: Usage,
+
+    /// Logical value.
+    pub value: i32,
+}
+
+/// A parsed HID report descriptor.
+///
+/// ```
+/// use nusb::hid::{ReportDescriptor, ReportType, Usage};
+///
+/// // Three buttons and a relative X / Y position.
+/// let mouse = ReportDescriptor::parse(&[
+///     0x05, 0x01, 0x09, 0x02, 0xa1, 0x01, 0x09, 0x01, 0xa1, 0x00, 0x05, 0x09,
+///     0x19, 0x01, 0x29, 0x03, 0x15, 0x00, 0x25, 0x01, 0x95, 0x03, 0x75, 0x01,
+///     0x81, 0x02, 0x95, 0x01, 0x75, 0x05, 0x81, 0x03, 0x05, 0x01, 0x09, 0x30,
+///     0x09, 0x31, 0x15, 0x81, 0x25, 0x7f, 0x75, 0x08, 0x95, 0x02, 0x81, 0x06,
+///     0xc0, 0xc0,
+/// ]).unwrap();
+///
+/// let report = mouse.report(ReportType::Input, None).unwrap();
+/// assert_eq!(report.size(), 3);
+/// assert_eq!(report.fields()[2].usages(), [Usage::new(0x01, 0x30), Usage::new(0x01, 0x31)]);
+/// assert_eq!(report.fields()[2].logical_minimum(), -127);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportDescriptor {
+    reports: Vec<Report>,
+    collections: Vec<Collection>,
+}
+
+impl ReportDescriptor {
+    /// Parse a report descriptor.
+    ///
+    /// ### Errors
+    ///  * when an item is truncated, or is not valid in its context
+    ///  * when a `Report Size` is larger than 32 bits, or a report is longer
+    ///    than 65535 bytes
+    pub fn parse(buf: &[u8]) -> Result<ReportDescriptor, ParseReportDescriptorError> {
+        Parser::default().parse(buf)
+    }
+
+    /// All reports, ordered by report ID and then by type.
+    pub fn reports(&self) -> impl Iterator<Item = &Report> {
+        self.reports.iter()
+    }
+
+    /// Find the report with the specified type and ID.
+    pub fn report(&self, report_type: ReportType, report_id: Option<u8>) -> Option<&Report> {
+        self.reports
+            .iter()
+            .find(|r| r.report_type == report_type && r.report_id == report_id)
+    }
+
+    /// Whether reports are prefixed with a report ID byte.
+    pub fn uses_report_ids(&self) -> bool {
+        self.reports.iter().any(|r| r.report_id.is_some())
+    }
+
+    /// All collections, in the order they appear in the descriptor.
+    pub fn collections(&self) -> &[Collection] {
+        &self.collections
+    }
+}
+
+/// Error returned from [`ReportDescriptor::parse`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseReportDescriptorError {
+    offset: usize,
+    message: &'static str,
+}
+
+impl ParseReportDescriptorError {
+    /// Byte offset of the item that could not be parsed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Display for ParseReportDescriptorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid HID report descriptor at offset {}: {}",
+            self.offset, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseReportDescriptorError {}
+
+impl From<ParseReportDescriptorError> for Error {
+    fn from(value: ParseReportDescriptorError) -> Self {
+        Error::new(ErrorKind::InvalidData, value)
+    }
+}
+
+/// Global item state, saved and restored by `Push` and `Pop`.
+#[derive(Clone, Default)]
+struct Globals {
+    usage_page: u16,
+    logical_minimum: ItemData,
+    logical_maximum: ItemData,
+    physical_minimum: ItemData,
+    physical_maximum: ItemData,
+    unit_exponent: ItemData,
+    unit: u32,
+    report_size: u32,
+    report_id: Option<u8>,
+    report_count: u32,
+}
+
+/// Data of a short item, which is signed or unsigned depending on context.
+#[derive(Copy, Clone, Default)]
+struct ItemData {
+    value: u32,
+    size: usize,
+}
+
+impl ItemData {
+    fn unsigned(&self) -> u32 {
+        self.value
+    }
+
+    fn signed(&self) -> i32 {
+        match self.size {
+            1 => self.value as u8 as i8 as i32,
+            2 => self.value as u16 as i16 as i32,
+            _ => self.value as i32,
+        }
+    }
+}
+
+/// Usage local item, resolved against the usage page when the main item is
+/// reached.
+#[derive(Copy, Clone)]
+enum LocalUsage {
+    Single(ItemData),
+    Range(ItemData, ItemData),
+}
+
+#[derive(Default)]
+struct Parser {
+    globals: Globals,
+    stack: Vec<Globals>,
+    usages: Vec<LocalUsage>,
+    usage_minimum: Option<ItemData>,
+    collections: Vec<Collection>,
+    open_collections: Vec<usize>,
+    reports: BTreeMap<(Option<u8>, u8), Report>,
+}
+
+impl Parser {
+    fn parse(mut self, buf: &[u8]) -> Result<ReportDescriptor, ParseReportDescriptorError> {
+        let mut offset = 0;
+
+        while offset < buf.len() {
+            let prefix = buf[offset];
+            let err = move |message| ParseReportDescriptorError { offset, message };
+
+            if prefix == ITEM_LONG {
+                let size = *buf.get(offset + 1).ok_or(err("truncated long item"))? as usize;
+                offset += 3 + size;
+                if offset > buf.len() {
+                    return Err(err("truncated long item"));
+                }
+                continue;
+            }
+
+            let size = match prefix & 0x03 {
+                3 => 4,
+                s => s as usize,
+            };
+            let data = buf
+                .get(offset + 1..offset + 1 + size)
+                .ok_or(err("truncated item"))?;
+            let mut value = [0; 4];
+            value[..size].copy_from_slice(data);
+            let data = ItemData {
+                value: u32::from_le_bytes(value),
+                size,
+            };
+
+            let tag = prefix >> 4;
+            match (prefix >> 2) & 0x03 {
+                ITEM_TYPE_MAIN => self.main(tag, data).map_err(err)?,
+                ITEM_TYPE_GLOBAL => self.global(tag, data).map_err(err)?,
+                ITEM_TYPE_LOCAL => self.local(tag, data).map_err(err)?,
+                _ => return Err(err("reserved item type")),
+            }
+
+            offset += 1 + size;
+        }
+
+        if !self.open_collections.is_empty() {
+            return Err(ParseReportDescriptorError {
+                offset,
+                message: "collection not closed",
+            });
+        }
+
+        Ok(ReportDescriptor {
+            reports: self.reports.into_values().collect(),
+            collections: self.collections,
+        })
+    }
+
+    fn main(&mut self, tag: u8, data: ItemData) -> Result<(), &'static str> {
+        match tag {
+            MAIN_INPUT => self.field(ReportType::Input, data)?,
+            MAIN_OUTPUT => self.field(ReportType::Output, data)?,
+            MAIN_FEATURE => self.field(ReportType::Feature, data)?,
+            MAIN_COLLECTION => {
+                let usage = self.resolve_usages()?.first().copied();
+                self.collections.push(Collection {
+                    kind: CollectionKind::from_u8(data.unsigned() as u8),
+                    usage,
+                    parent: self.open_collections.last().copied(),
+                });
+                self.open_collections.push(self.collections.len() - 1);
+            }
+            MAIN_END_COLLECTION => {
+                self.open_collections
+                    .pop()
+                    .ok_or("end collection without collection")?;
+            }
+            _ => return Err("reserved main item"),
+        }
+
+        self.usages.clear();
+        self.usage_minimum = None;
+        Ok(())
+    }
+
+    fn field(&mut self, report_type: ReportType, data: ItemData) -> Result<(), &'static str> {
+        let g = &self.globals;
+        if g.report_size > MAX_REPORT_SIZE {
+            return Err("report size larger than 32 bits");
+        }
+
+        let bits = g
+            .report_size
+            .checked_mul(g.report_count)
+            .filter(|&b| b <= MAX_REPORT_BITS)
+            .ok_or("report too long")?;
+
+        let logical_minimum = g.logical_minimum.signed();
+        let logical_maximum = if logical_minimum >= 0 {
+            g.logical_maximum.unsigned() as i32
+        } else {
+            g.logical_maximum.signed()
+        };
+        let physical_minimum = g.physical_minimum.signed();
+        let physical_maximum = if physical_minimum >= 0 {
+            g.physical_maximum.unsigned() as i32
+        } else {
+            g.physical_maximum.signed()
+        };
+        let unit_exponent = match g.unit_exponent.unsigned() {
+            // Usually a 4-bit two's complement value
+            v @ 0..=15 => ((v as i8) << 4) >> 4,
+            _ => g.unit_exponent.signed() as i8,
+        };
+        let (report_id, report_size, report_count, unit) =
+            (g.report_id, g.report_size, g.report_count, g.unit);
+
+        let usages = self.resolve_usages()?;
+
+        let report = self
+            .reports
+            .entry((report_id, report_type as u8))
+            .or_insert_with(|| Report {
+                report_type,
+                report_id,
+                bit_len: 0,
+                fields: Vec::new(),
+            });
+
+        let bit_offset = report.bit_len;
+        report.bit_len = bit_offset
+            .checked_add(bits)
+            .filter(|&b| b <= MAX_REPORT_BITS)
+            .ok_or("report too long")?;
+
+        if bits > 0 {
+            report.fields.push(Field {
+                bit_offset,
+                bit_size: report_size,
+                count: report_count,
+                flags: data.unsigned(),
+                usages,
+                logical_minimum,
+                logical_maximum,
+                physical_minimum,
+                physical_maximum,
+                unit,
+                unit_exponent,
+                collection: self.open_collections.last().copied(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn global(&mut self, tag: u8, data: ItemData) -> Result<(), &'static str> {
+        let g = &mut self.globals;
+        match tag {
+            GLOBAL_USAGE_PAGE => g.usage_page = data.unsigned() as u16,
+            GLOBAL_LOGICAL_MINIMUM => g.logical_minimum = data,
+            GLOBAL_LOGICAL_MAXIMUM => g.logical_maximum = data,
+            GLOBAL_PHYSICAL_MINIMUM => g.physical_minimum = data,
+            GLOBAL_PHYSICAL_MAXIMUM => g.physical_maximum = data,
+            GLOBAL_UNIT_EXPONENT => g.unit_exponent = data,
+            GLOBAL_UNIT => g.unit = data.unsigned(),
+            GLOBAL_REPORT_SIZE => g.report_size = data.unsigned(),
+            GLOBAL_REPORT_ID => {
+                let id = u8::try_from(data.unsigned())
+                    .ok()
+                    .filter(|&id| id != 0)
+                    .ok_or("invalid report ID")?;
+                g.report_id = Some(id);
+            }
+            GLOBAL_REPORT_COUNT => g.report_count = data.unsigned(),
+            GLOBAL_PUSH => self.stack.push(self.globals.clone()),
+            GLOBAL_POP => self.globals = self.stack.pop().ok_or("pop without push")?,
+            _ => return Err("reserved global item"),
+        }
+        Ok(())
+    }
+
+    fn local(&mut self, tag: u8, data: ItemData) -> Result<(), &'static str> {
+        match tag {
+            LOCAL_USAGE => self.usages.push(LocalUsage::Single(data)),
+            LOCAL_USAGE_MINIMUM => self.usage_minimum = Some(data),
+            LOCAL_USAGE_MAXIMUM => {
+                let min = self
+                    .usage_minimum
+                    .take()
+                    .ok_or("usage maximum without usage minimum")?;
+                self.usages.push(LocalUsage::Range(min, data));
+            }
+            // Designators, strings, and delimiters are not used for decoding
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Expand the usage local items, applying the current usage page to
+    /// those that don't include one.
+    fn resolve_usages(&self) -> Result<Vec<Usage>, &'static str> {
+        let resolve = |d: ItemData| {
+            if d.size == 4 {
+                d.value
+            } else {
+                ((self.globals.usage_page as u32) << 16) | (d.value & 0xFFFF)
+            }
+        };
+
+        let mut usages = Vec::new();
+        for usage in &self.usages {
+            match *usage {
+                LocalUsage::Single(d) => usages.push(Usage(resolve(d))),
+                LocalUsage::Range(min, max) => {
+                    let (min, max) = (resolve(min), resolve(max));
+                    if max < min || max - min > MAX_USAGE_RANGE {
+                        return Err("invalid usage range");
+                    }
+                    usages.extend((min..=max).map(Usage));
+                }
+            }
+        }
+        Ok(usages)
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_boot_keyboard() {
+    // HID 1.11 appendix B.1
+    let desc = ReportDescriptor::parse(&[
+        0x05, 0x01, 0x09, 0x06, 0xa1, 0x01, 0x05, 0x07, 0x19, 0xe0, 0x29, 0xe7,
+        0x15, 0x00, 0x25, 0x01, 0x75, 0x01, 0x95, 0x08, 0x81, 0x02, 0x95, 0x01,
+        0x75, 0x08, 0x81, 0x01, 0x95, 0x05, 0x75, 0x01, 0x05, 0x08, 0x19, 0x01,
+        0x29, 0x05, 0x91, 0x02, 0x95, 0x01, 0x75, 0x03, 0x91, 0x01, 0x95, 0x06,
+        0x75, 0x08, 0x15, 0x00, 0x25, 0x65, 0x05, 0x07, 0x19, 0x00, 0x29, 0x65,
+        0x81, 0x00, 0xc0,
+    ]).unwrap();
+
+    assert!(!desc.uses_report_ids());
+    assert_eq!(desc.collections().len(), 1);
+    assert_eq!(desc.collections()[0].kind(), CollectionKind::Application);
+    assert_eq!(desc.collections()[0].usage(), Some(Usage::new(0x01, 0x06)));
+
+    let input = desc.report(ReportType::Input, None).unwrap();
+    assert_eq!(input.size(), 8);
+    let fields = input.fields();
+    assert_eq!(fields.len(), 3);
+
+    assert!(fields[0].is_variable());
+    assert_eq!((fields[0].bit_offset(), fields[0].bit_size(), fields[0].count()), (0, 1, 8));
+    assert_eq!(fields[0].usage(0), Some(Usage::new(0x07, 0xe0)));
+    assert_eq!(fields[0].usage(7), Some(Usage::new(0x07, 0xe7)));
+
+    assert!(fields[1].is_constant());
+    assert_eq!(fields[1].bit_offset(), 8);
+
+    assert!(!fields[2].is_variable());
+    assert_eq!((fields[2].bit_offset(), fields[2].bit_size(), fields[2].count()), (16, 8, 6));
+    assert_eq!((fields[2].logical_minimum(), fields[2].logical_maximum()), (0, 0x65));
+    assert_eq!(fields[2].usages().len(), 0x66);
+    assert_eq!(fields[2].collection(), Some(0));
+
+    let output = desc.report(ReportType::Output, None).unwrap();
+    assert_eq!(output.size(), 1);
+    assert_eq!(output.fields()[0].usages()[0], Usage::new(0x08, 0x01));
+    assert!(desc.report(ReportType::Feature, None).is_none());
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_report_ids() {
+    let desc = ReportDescriptor::parse(&[
+        0x05, 0x0c, 0x09, 0x01, 0xa1, 0x01,
+        0x85, 0x02, 0x15, 0x00, 0x26, 0xff, 0x03, 0x75, 0x10, 0x95, 0x01,
+        0x19, 0x00, 0x2a, 0xff, 0x03, 0x81, 0x00,
+        0xa4, 0x85, 0x05, 0x06, 0x00, 0xff, 0x09, 0x01, 0x75, 0x08, 0x95, 0x3f,
+        0xb1, 0x02, 0xb4,
+        0x09, 0xe9, 0x75, 0x01, 0x95, 0x01, 0x81, 0x02,
+        0xc0,
+    ]).unwrap();
+
+    assert!(desc.uses_report_ids());
+    let ids: Vec<_> = desc.reports().map(|r| (r.report_id(), r.report_type())).collect();
+    assert_eq!(ids, [(Some(2), ReportType::Input), (Some(5), ReportType::Feature)]);
+
+    let consumer = desc.report(ReportType::Input, Some(2)).unwrap();
+    assert_eq!(consumer.size(), 3);
+    assert_eq!(consumer.fields()[0].logical_maximum(), 0x3ff);
+    assert_eq!(consumer.fields()[0].usages().len(), 0x400);
+    assert_eq!(consumer.fields()[1].usages(), [Usage::new(0x0c, 0xe9)]);
+    assert_eq!(consumer.fields()[1].bit_offset(), 16);
+
+    let vendor = desc.report(ReportType::Feature, Some(5)).unwrap();
+    assert_eq!(vendor.size(), 63);
+    assert_eq!(vendor.fields()[0].usage(10), Some(Usage::new(0xff00, 0x01)));
+}
+
+#[test]
+fn test_errors() {
+    let err = ReportDescriptor::parse(&[0x05, 0x01, 0xa1, 0x01, 0x26, 0xff]).unwrap_err();
+    assert_eq!(err.offset(), 4);
+    assert_eq!(
+        err.to_string(),
+        "invalid HID report descriptor at offset 4: truncated item"
+    );
+
+    let err = ReportDescriptor::parse(&[0xa1, 0x01]).unwrap_err();
+    assert_eq!(err.offset(), 2);
+
+    let err = ReportDescriptor::parse(&[0x75, 0x40, 0x95, 0x01, 0x81, 0x02]).unwrap_err();
+    assert_eq!(err.offset(), 4);
+
+    let err = ReportDescriptor::parse(&[0xc0]).unwrap_err();
+    assert_eq!(err.offset(), 0);
+}
+
+#[test]
+#[rustfmt::skip]
+fn test_decode() {
+    // Three buttons and a relative X / Y position.
+    let desc = ReportDescriptor::parse(&[
+        0x05, 0x01, 0x09, 0x02, 0xa1, 0x01, 0x09, 0x01, 0xa1, 0x00, 0x05, 0x09,
+        0x19, 0x01, 0x29, 0x03, 0x15, 0x00, 0x25, 0x01, 0x95, 0x03, 0x75, 0x01,
+        0x81, 0x02, 0x95, 0x01, 0x75, 0x05, 0x81, 0x03, 0x05, 0x01, 0x09, 0x30,
+        0x09, 0x31, 0x15, 0x81, 0x25, 0x7f, 0x75, 0x08, 0x95, 0x02, 0x81, 0x06,
+        0xc0, 0xc0,
+    ]).unwrap();
+    let report = desc.report(ReportType::Input, None).unwrap();
+
+    assert_eq!(
+        report.decode(&[0b101, 0x05, 0xfe]),
+        [
+            ReportValue { usage: Usage::new(0x09, 1), value: 1 },
+            ReportValue { usage: Usage::new(0x09, 2), value: 0 },
+            ReportValue { usage: Usage::new(0x09, 3), value: 1 },
+            ReportValue { usage: Usage::new(0x01, 0x30), value: 5 },
+            ReportValue { usage: Usage::new(0x01, 0x31), value: -2 },
+        ]
+    );
+    assert_eq!(report.decode(&[0b010]).len(), 3);
+
+    // Keyboard array with usage 0 meaning no key.
+    let desc = ReportDescriptor::parse(&[
+        0x05, 0x07, 0x15, 0x00, 0x25, 0x65, 0x19, 0x00, 0x29, 0x65, 0x75, 0x08,
+        0x95, 0x03, 0x81, 0x00,
+    ]).unwrap();
+    let report = desc.report(ReportType::Input, None).unwrap();
+    assert_eq!(
+        report.decode(&[0x04, 0x00, 0x70]),
+        [ReportValue { usage: Usage::new(0x07, 0x04), value: 1 }]
+    );
+
+    // Values straddling byte boundaries.
+    let desc = ReportDescriptor::parse(&[
+        0x05, 0x01, 0x09, 0x30, 0x09, 0x31, 0x15, 0x00, 0x26, 0xff, 0x0f, 0x75,
+        0x0c, 0x95, 0x02, 0x81, 0x02,
+    ]).unwrap();
+    let field = &desc.report(ReportType::Input, None).unwrap().fields()[0];
+    assert_eq!(field.value(&[0x23, 0xc1, 0xab], 0), Some(0x123));
+    assert_eq!(field.value(&[0x23, 0xc1, 0xab], 1), Some(0xabc));
+    assert_eq!(field.value(&[0x23, 0xc1], 1), None);
+    assert_eq!(field.value(&[0x23, 0xc1, 0xab], 2), None);
+}
@@ -3,15 +3,128 @@
 //!
 //! See [`super::watch_devices`] for a usage example.
 
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::Instant,
+};
+
 use futures_core::Stream;
 
-use crate::{DeviceId, DeviceInfo};
+use crate::{DeviceFilter, DeviceId, DeviceInfo};
 
 /// Stream of device connection / disconnection events.
 ///
 /// Call [`super::watch_devices`] to begin watching device
-/// events and create a `HotplugWatch`.
-pub struct HotplugWatch(pub(crate) crate::platform::HotplugWatch);
+/// events and create a `HotplugWatch`, or
+/// [`super::watch_devices_matching`] to receive events only for devices
+/// matching a [`DeviceFilter`].
+pub struct HotplugWatch {
+    inner: crate::platform::HotplugWatch,
+    filter: Option<WatchFilter>,
+
+    /// Last known information about the connected devices, used to fill in
+    /// `Disconnected` events.
+    known: HashMap<DeviceId, DeviceInfo>,
+}
+
+/// Filter applied to a [`HotplugWatch`], along with the IDs of connected
+/// devices that matched it, needed to filter disconnect events.
+struct WatchFilter {
+    filter: DeviceFilter,
+    matched: HashSet<DeviceId>,
+}
+
+impl HotplugWatch {
+    /// Create a watch, given the devices that were connected when `inner`
+    /// started watching.
+    pub(crate) fn new(
+        inner: crate::platform::HotplugWatch,
+        connected: impl Iterator<Item = DeviceInfo>,
+    ) -> HotplugWatch {
+        HotplugWatch {
+            inner,
+            filter: None,
+            known: connected.map(|d| (d.id(), d)).collect(),
+        }
+    }
+
+    /// Only return events for devices matching `filter`, given the currently
+    /// connected devices that match it.
+    pub(crate) fn with_filter(
+        mut self,
+        filter: DeviceFilter,
+        connected: impl Iterator<Item = DeviceId>,
+    ) -> HotplugWatch {
+        self.filter = Some(WatchFilter {
+            filter,
+            matched: connected.collect(),
+        });
+        self
+    }
+
+    /// Get the next event if one is available, or register `cx` for wakeup
+    /// when one becomes available.
+    pub(crate) fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<HotplugEvent> {
+        loop {
+            let event = match self.inner.poll_next(cx) {
+                Poll::Ready(HotplugEvent::Connected(info)) => {
+                    self.known.insert(info.id(), info.clone());
+                    HotplugEvent::Connected(info)
+                }
+                Poll::Ready(HotplugEvent::Disconnected(id, _)) => {
+                    HotplugEvent::Disconnected(id, self.known.remove(&id))
+                }
+                Poll::Ready(event) => event,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let Some(f) = &mut self.filter else {
+                return Poll::Ready(event);
+            };
+
+            match &event {
+                HotplugEvent::Connected(d) if f.filter.matches(d) => {
+                    f.matched.insert(d.id());
+                    return Poll::Ready(event);
+                }
+                HotplugEvent::Disconnected(id, _) if f.matched.remove(id) => {
+                    return Poll::Ready(event);
+                }
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                HotplugEvent::DriverBound(c)
+                | HotplugEvent::DriverUnbound(c)
+                | HotplugEvent::Changed(c)
+                    if f.matched.contains(&c.device_id) =>
+                {
+                    return Poll::Ready(event);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Block the current thread until the next event arrives or `deadline`
+    /// passes.
+    pub(crate) fn next_until(&mut self, deadline: Instant) -> Option<HotplugEvent> {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            if let Poll::Ready(event) = self.poll_event(&mut cx) {
+                return Some(event);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+}
 
 impl Stream for HotplugWatch {
     type Item = HotplugEvent;
@@ -20,16 +133,79 @@ impl Stream for HotplugWatch {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        self.0.poll_next(cx).map(Some)
+        self.poll_event(cx).map(Some)
     }
 }
 
 /// Event returned from the [`HotplugWatch`] stream.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum HotplugEvent {
     /// A device has been connected.
     Connected(DeviceInfo),
 
     /// A device has been disconnected.
-    Disconnected(DeviceId),
+    ///
+    /// Along with its ID, this contains the last known information about the
+    /// device: from its `Connected` event, or from when the watch was created
+    /// if it was already connected. It is `None` if the device connected and
+    /// disconnected while the watch was being created.
+    Disconnected(DeviceId, Option<DeviceInfo>),
+
+    /// *(Linux-only)* A kernel driver was bound to a device or interface.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    DriverBound(DeviceChange),
+
+    /// *(Linux-only)* A kernel driver was unbound from a device or interface.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    DriverUnbound(DeviceChange),
+
+    /// *(Linux-only)* The kernel reported a change to a device or interface,
+    /// such as its configuration being changed.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Changed(DeviceChange),
+}
+
+/// *(Linux-only)* Details of a [`HotplugEvent::DriverBound`],
+/// [`HotplugEvent::DriverUnbound`], or [`HotplugEvent::Changed`] event.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug, Clone)]
+pub struct DeviceChange {
+    pub(crate) device_id: DeviceId,
+    pub(crate) interface_number: Option<u8>,
+    pub(crate) driver: Option<String>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl DeviceChange {
+    /// The device that the event applies to, or the device containing the
+    /// interface.
+    pub fn device_id(&self) -> DeviceId {
+        self.device_id
+    }
+
+    /// The `bInterfaceNumber` of the interface that the event applies to, or
+    /// `None` if it applies to the device itself.
+    pub fn interface_number(&self) -> Option<u8> {
+        self.interface_number
+    }
+
+    /// Name of the kernel driver, e.g. `usbhid` or `cdc_acm`.
+    ///
+    /// The kernel does not report the driver in `unbind` events, so for
+    /// [`HotplugEvent::DriverUnbound`] this is the driver that was bound when
+    /// the watch was created or seen in a later `DriverBound` event. It is
+    /// `None` if no driver is bound or it is unknown.
+    pub fn driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
+}
+
+/// Waker that unparks a thread blocked in [`HotplugWatch::next_until`].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
 }
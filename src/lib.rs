@@ -114,7 +114,10 @@
 //! Users have access to USB devices by default, with no permission configuration needed.
 //! Devices with a kernel driver are not accessible.
 
-use std::io;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
 
 mod platform;
 
@@ -122,13 +125,29 @@ pub mod descriptors;
 mod enumeration;
 pub use enumeration::{BusInfo, DeviceId, DeviceInfo, InterfaceInfo, Speed, UsbControllerType};
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use enumeration::{AuthorizedDefault, EndpointInfo, PortConnectType, PortInfo};
+
 mod device;
 pub use device::{Device, Interface};
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use platform::Enumerator;
+
+mod filter;
+pub use filter::{DeviceFilter, ParseFilterError};
+
 pub mod transfer;
 
+pub mod cdc;
+pub mod hid;
+
 pub mod hotplug;
 
+pub mod managed;
+
+pub mod monitor;
+
 /// OS error returned from operations other than transfers.
 pub type Error = io::Error;
 
@@ -146,6 +165,23 @@ pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
     platform::list_devices()
 }
 
+/// Get an iterator listing the connected devices that match `filter`.
+///
+/// ### Example
+///
+/// ```no_run
+/// use nusb::DeviceFilter;
+/// let filter = DeviceFilter::new().vendor_id(0xAAAA).product_id(0xBBBB);
+/// let device = nusb::list_devices_matching(&filter).unwrap()
+///     .next()
+///     .expect("device not connected");
+/// ```
+pub fn list_devices_matching(
+    filter: &DeviceFilter,
+) -> Result<impl Iterator<Item = DeviceInfo> + '_, Error> {
+    Ok(list_devices()?.filter(move |d| filter.matches(d)))
+}
+
 /// Get an iterator listing the system USB buses.
 ///
 /// ### Example
@@ -180,6 +216,11 @@ pub fn list_buses() -> Result<impl Iterator<Item = BusInfo>, Error> {
 /// call [`list_devices`] after creating the watch with this function to avoid
 /// potentially missing a newly-attached device:
 ///
+/// Creating the watch also lists the connected devices, so that
+/// `Disconnected` events can include their last known [`DeviceInfo`]. This
+/// costs the same as a call to [`list_devices`], and if listing devices fails,
+/// this function returns that error.
+///
 /// ## Example
 ///
 /// ```no_run
@@ -193,9 +234,10 @@ pub fn list_buses() -> Result<impl Iterator<Item = BusInfo>, Error> {
 ///         HotplugEvent::Connected(d) => {
 ///             devices.insert(d.id(), d);
 ///         }
-///         HotplugEvent::Disconnected(id) => {
+///         HotplugEvent::Disconnected(id, _) => {
 ///             devices.remove(&id);
 ///         }
+///         _ => {}
 ///     }
 /// }
 /// ```
@@ -206,6 +248,71 @@ pub fn list_buses() -> Result<impl Iterator<Item = BusInfo>, Error> {
 ///     when the `Connected` event is emitted. If you are immediately opening the device
 ///     and claiming an interface when receiving a `Connected` event,
 ///     you should retry after a short delay if opening or claiming fails.
+///   * On Linux, events are also returned when a kernel driver is bound to or
+///     unbound from a device or interface, and when the kernel reports a
+///     change such as a new active configuration.
+///   * On Linux, if the udev netlink socket can't be opened, such as in some
+///     containers and sandboxes, device nodes appearing and disappearing in
+///     `/dev/bus/usb` are watched instead. Only `Connected` and
+///     `Disconnected` events are reported in this case.
 pub fn watch_devices() -> Result<hotplug::HotplugWatch, Error> {
-    Ok(hotplug::HotplugWatch(platform::HotplugWatch::new()?))
+    // List devices after starting the watch so that every device that
+    // disconnects later is known.
+    let watch = platform::HotplugWatch::new()?;
+    Ok(hotplug::HotplugWatch::new(watch, platform::list_devices()?))
+}
+
+/// Get a [`Stream`][`futures_core::Stream`] that yields an
+/// [event][`hotplug::HotplugEvent`] when a USB device matching `filter` is
+/// connected or disconnected.
+///
+/// `Disconnected` events are returned for devices that matched the filter
+/// when they connected, including those already connected when this function
+/// is called.
+///
+/// See [`watch_devices`] for details and platform-specific notes.
+pub fn watch_devices_matching(filter: DeviceFilter) -> Result<hotplug::HotplugWatch, Error> {
+    let watch = watch_devices()?;
+    let connected = list_devices_matching(&filter)?
+        .map(|d| d.id())
+        .collect::<Vec<_>>();
+    Ok(watch.with_filter(filter, connected.into_iter()))
+}
+
+/// Wait for a device matching `filter` to be connected.
+///
+/// Returns immediately if a matching device is already connected. Blocks until
+/// one connects or the `timeout` elapses, in which case an error with kind
+/// [`TimedOut`][`std::io::ErrorKind::TimedOut`] is returned.
+///
+/// ### Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// let filter = "aaaa:bbbb".parse().unwrap();
+/// let info = nusb::wait_for_device(&filter, Duration::from_secs(10)).unwrap();
+/// let device = info.open().unwrap();
+/// ```
+pub fn wait_for_device(filter: &DeviceFilter, timeout: Duration) -> Result<DeviceInfo, Error> {
+    let deadline = Instant::now() + timeout;
+
+    // Start watching before listing devices so a newly-attached device
+    // can't be missed.
+    let mut watch = watch_devices()?;
+    if let Some(info) = list_devices_matching(filter)?.next() {
+        return Ok(info);
+    }
+
+    while let Some(event) = watch.next_until(deadline) {
+        if let hotplug::HotplugEvent::Connected(info) = event {
+            if filter.matches(&info) {
+                return Ok(info);
+            }
+        }
+    }
+
+    Err(Error::new(
+        io::ErrorKind::TimedOut,
+        "no matching device connected before the timeout",
+    ))
 }
@@ -0,0 +1,404 @@
+//! A device handle that reconnects automatically.
+//!
+//! See [`ManagedDevice`].
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use log::{debug, warn};
+
+use crate::{
+    hotplug::{HotplugEvent, HotplugWatch},
+    Device, DeviceId, DeviceInfo, Error, Interface,
+};
+
+/// A handle to a device that is re-opened and set up again each time it
+/// reconnects.
+///
+/// A `ManagedDevice` is created with a matcher that selects the device from
+/// [`DeviceInfo`]s, and a setup closure that is called with the opened
+/// [`Device`] each time a matching device connects. The setup closure claims
+/// the interface, selects an alternate setting, and does any other
+/// initialization the device needs, and returns the [`Interface`] used to
+/// talk to it.
+///
+/// The `ManagedDevice` is a [`Stream`] of [`ConnectionEvent`]s. Poll it
+/// continuously (e.g. alongside your application's other work) to keep the
+/// connection state up to date, and use [`interface`][`Self::interface`] to
+/// get the interface for the currently-connected device.
+///
+/// Only one matching device is managed at a time. If multiple matching devices
+/// are connected, the first one found is used and the others are ignored
+/// until it disconnects.
+///
+/// ### Example
+///
+/// ```no_run
+/// use futures_lite::stream::StreamExt;
+/// use nusb::managed::{ConnectionEvent, ManagedDevice};
+/// # futures_lite::future::block_on(async {
+/// let mut device = ManagedDevice::new(
+///     |d| d.vendor_id() == 0xAAAA && d.product_id() == 0xBBBB,
+///     |device| {
+///         let interface = device.claim_interface(0)?;
+///         interface.set_alt_setting(1)?;
+///         Ok(interface)
+///     },
+/// ).unwrap();
+///
+/// while let Some(event) = device.next().await {
+///     match event {
+///         ConnectionEvent::Connected(info) => {
+///             println!("connected: {info:?}");
+///             let interface = device.interface().unwrap();
+///             // start transfers on `interface`
+///         }
+///         ConnectionEvent::Disconnected(info) => println!("disconnected: {info:?}"),
+///         ConnectionEvent::SetupFailed(info, e) => println!("failed to set up {info:?}: {e}"),
+///     }
+/// }
+/// # })
+/// ```
+///
+/// ### Platform-specific notes
+///
+///   * On Windows, the interfaces of a composite device might not be ready
+///     when the device connects, causing the setup closure to fail with
+///     [`ConnectionEvent::SetupFailed`]. Call [`rescan`][`Self::rescan`]
+///     after a short delay to try again.
+pub struct ManagedDevice {
+    setup: Box<SetupFn>,
+    watch: HotplugWatch,
+    state: State<Connection>,
+}
+
+type SetupFn = dyn FnMut(&Device) -> Result<Interface, Error> + Send;
+
+struct Connection {
+    device: Device,
+    interface: Interface,
+}
+
+impl ManagedDevice {
+    /// Start managing the first device for which `matcher` returns `true`.
+    ///
+    /// If a matching device is already connected, it is opened and set up
+    /// immediately, and the first event returned from the stream reports the
+    /// result.
+    pub fn new(
+        matcher: impl FnMut(&DeviceInfo) -> bool + Send + 'static,
+        setup: impl FnMut(&Device) -> Result<Interface, Error> + Send + 'static,
+    ) -> Result<ManagedDevice, Error> {
+        // Start watching before listing devices so a newly-attached device
+        // can't be missed.
+        let watch = crate::watch_devices()?;
+
+        let mut managed = ManagedDevice {
+            setup: Box::new(setup),
+            watch,
+            state: State::new(Box::new(matcher)),
+        };
+        managed.rescan()?;
+        Ok(managed)
+    }
+
+    /// The interface returned by the setup closure for the currently
+    /// connected device, or `None` if no matching device is connected.
+    ///
+    /// Transfers on this interface fail with
+    /// [`TransferError::Disconnected`][`crate::transfer::TransferError::Disconnected`]
+    /// once the device disconnects. Keep polling the stream to receive the
+    /// interface for the next connection.
+    pub fn interface(&self) -> Option<&Interface> {
+        self.state.current.as_ref().map(|(_, c)| &c.interface)
+    }
+
+    /// The currently connected device, or `None` if no matching device is
+    /// connected.
+    pub fn device(&self) -> Option<&Device> {
+        self.state.current.as_ref().map(|(_, c)| &c.device)
+    }
+
+    /// Information about the currently connected device, or `None` if no
+    /// matching device is connected.
+    pub fn device_info(&self) -> Option<&DeviceInfo> {
+        self.state.current.as_ref().map(|(info, _)| info)
+    }
+
+    /// Whether a matching device is connected and set up.
+    pub fn is_connected(&self) -> bool {
+        self.state.current.is_some()
+    }
+
+    /// Search the connected devices for a matching device if none is
+    /// currently set up.
+    ///
+    /// This is done automatically when the `ManagedDevice` is created, when
+    /// devices connect, and when the managed device disconnects. Call it to
+    /// retry after [`ConnectionEvent::SetupFailed`]. The result is returned
+    /// from the stream.
+    pub fn rescan(&mut self) -> Result<(), Error> {
+        self.state.rescan(&mut System(&mut self.setup), None)
+    }
+}
+
+impl Stream for ManagedDevice {
+    type Item = ConnectionEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(event) = this.state.pending.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+
+            match this.watch.poll_event(cx) {
+                Poll::Ready(event) => this.state.handle_event(event, &mut System(&mut this.setup)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Lists and opens devices for [`State`].
+trait Host<C> {
+    fn list_devices(&mut self) -> Result<Vec<DeviceInfo>, Error>;
+    fn open(&mut self, info: &DeviceInfo) -> Result<C, Error>;
+}
+
+/// The real [`Host`], opening devices and calling the setup closure.
+struct System<'a>(&'a mut SetupFn);
+
+impl Host<Connection> for System<'_> {
+    fn list_devices(&mut self) -> Result<Vec<DeviceInfo>, Error> {
+        Ok(crate::list_devices()?.collect())
+    }
+
+    fn open(&mut self, info: &DeviceInfo) -> Result<Connection, Error> {
+        let device = info.open()?;
+        let interface = (self.0)(&device)?;
+        Ok(Connection { device, interface })
+    }
+}
+
+/// Which device is managed, and the events not yet returned from the stream.
+///
+/// This is kept separate from the OS through [`Host`] so it can be tested.
+struct State<C> {
+    matcher: Box<dyn FnMut(&DeviceInfo) -> bool + Send>,
+    current: Option<(DeviceInfo, C)>,
+    pending: VecDeque<ConnectionEvent>,
+}
+
+impl<C> State<C> {
+    fn new(matcher: Box<dyn FnMut(&DeviceInfo) -> bool + Send>) -> Self {
+        State {
+            matcher,
+            current: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// If no device is set up, set up the first connected device that
+    /// matches, other than `exclude`.
+    fn rescan(&mut self, host: &mut dyn Host<C>, exclude: Option<DeviceId>) -> Result<(), Error> {
+        if self.current.is_some() {
+            return Ok(());
+        }
+
+        if let Some(info) = host
+            .list_devices()?
+            .into_iter()
+            .find(|d| Some(d.id()) != exclude && (self.matcher)(d))
+        {
+            self.connect(info, host);
+        }
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: HotplugEvent, host: &mut dyn Host<C>) {
+        match event {
+            HotplugEvent::Connected(info) if self.current.is_none() && (self.matcher)(&info) => {
+                self.connect(info, host);
+            }
+            HotplugEvent::Disconnected(id, _)
+                if self
+                    .current
+                    .as_ref()
+                    .is_some_and(|(info, _)| info.id() == id) =>
+            {
+                let (info, _) = self.current.take().unwrap();
+                debug!("Managed device disconnected: {info:?}");
+                self.pending.push_back(ConnectionEvent::Disconnected(info));
+
+                // Another matching device may already be connected.
+                if let Err(e) = self.rescan(host, Some(id)) {
+                    warn!("Failed to list devices after managed device disconnected: {e}");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn connect(&mut self, info: DeviceInfo, host: &mut dyn Host<C>) {
+        let event = match host.open(&info) {
+            Ok(connection) => {
+                debug!("Managed device connected: {info:?}");
+                self.current = Some((info.clone(), connection));
+                ConnectionEvent::Connected(info)
+            }
+            Err(e) => {
+                warn!("Failed to set up managed device {info:?}: {e}");
+                ConnectionEvent::SetupFailed(info, e)
+            }
+        };
+        self.pending.push_back(event);
+    }
+}
+
+/// Event returned from the [`ManagedDevice`] stream.
+#[derive(Debug)]
+pub enum ConnectionEvent {
+    /// A matching device connected and was set up successfully.
+    ///
+    /// Its interface is available from [`ManagedDevice::interface`].
+    Connected(DeviceInfo),
+
+    /// The managed device disconnected.
+    Disconnected(DeviceInfo),
+
+    /// A matching device connected, but opening it or the setup closure
+    /// failed.
+    SetupFailed(DeviceInfo, Error),
+}
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::{ConnectionEvent, Host, State};
+    use crate::{hotplug::HotplugEvent, platform::device_info, DeviceId, DeviceInfo, Error};
+
+    /// Devices that are connected, and which of them fail to open.
+    #[derive(Default)]
+    struct FakeHost {
+        devices: Vec<DeviceInfo>,
+        broken: Vec<DeviceId>,
+    }
+
+    impl Host<()> for FakeHost {
+        fn list_devices(&mut self) -> Result<Vec<DeviceInfo>, Error> {
+            Ok(self.devices.clone())
+        }
+
+        fn open(&mut self, info: &DeviceInfo) -> Result<(), Error> {
+            if self.broken.contains(&info.id()) {
+                Err(Error::new(ErrorKind::PermissionDenied, "broken"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn state() -> State<()> {
+        State::new(Box::new(|d| d.vendor_id() == 0x1209))
+    }
+
+    fn events(state: &mut State<()>) -> Vec<(&'static str, DeviceId)> {
+        state
+            .pending
+            .drain(..)
+            .map(|e| match e {
+                ConnectionEvent::Connected(d) => ("connected", d.id()),
+                ConnectionEvent::Disconnected(d) => ("disconnected", d.id()),
+                ConnectionEvent::SetupFailed(d, _) => ("failed", d.id()),
+            })
+            .collect()
+    }
+
+    fn current(state: &State<()>) -> Option<DeviceId> {
+        state.current.as_ref().map(|(d, _)| d.id())
+    }
+
+    #[test]
+    fn test_connect_and_disconnect() {
+        let a = device_info(1, 2, 0x1209, 1);
+        let other = device_info(1, 3, 0x04b4, 1);
+        let mut host = FakeHost::default();
+        let mut state = state();
+
+        state.rescan(&mut host, None).unwrap();
+        assert_eq!(events(&mut state), []);
+
+        state.handle_event(HotplugEvent::Connected(other.clone()), &mut host);
+        assert_eq!(events(&mut state), []);
+
+        host.devices = vec![other.clone(), a.clone()];
+        state.handle_event(HotplugEvent::Connected(a.clone()), &mut host);
+        assert_eq!(events(&mut state), [("connected", a.id())]);
+        assert_eq!(current(&state), Some(a.id()));
+
+        host.devices = vec![other.clone()];
+        state.handle_event(HotplugEvent::Disconnected(other.id(), None), &mut host);
+        assert_eq!(events(&mut state), []);
+
+        state.handle_event(HotplugEvent::Disconnected(a.id(), None), &mut host);
+        assert_eq!(events(&mut state), [("disconnected", a.id())]);
+        assert_eq!(current(&state), None);
+    }
+
+    #[test]
+    fn test_second_device_after_disconnect() {
+        let a = device_info(1, 2, 0x1209, 1);
+        let b = device_info(1, 3, 0x1209, 1);
+        let mut host = FakeHost {
+            devices: vec![a.clone(), b.clone()],
+            ..Default::default()
+        };
+        let mut state = state();
+
+        state.rescan(&mut host, None).unwrap();
+        assert_eq!(events(&mut state), [("connected", a.id())]);
+
+        // Ignored while `a` is connected.
+        state.handle_event(HotplugEvent::Connected(b.clone()), &mut host);
+        assert_eq!(events(&mut state), []);
+        assert_eq!(current(&state), Some(a.id()));
+
+        // `b` is picked up without another hotplug event, even if `a` is
+        // still listed.
+        state.handle_event(HotplugEvent::Disconnected(a.id(), None), &mut host);
+        assert_eq!(
+            events(&mut state),
+            [("disconnected", a.id()), ("connected", b.id())]
+        );
+        assert_eq!(current(&state), Some(b.id()));
+    }
+
+    #[test]
+    fn test_setup_failed() {
+        let a = device_info(1, 2, 0x1209, 1);
+        let mut host = FakeHost {
+            devices: vec![a.clone()],
+            broken: vec![a.id()],
+        };
+        let mut state = state();
+
+        state.rescan(&mut host, None).unwrap();
+        assert_eq!(events(&mut state), [("failed", a.id())]);
+        assert_eq!(current(&state), None);
+
+        host.broken.clear();
+        state.rescan(&mut host, None).unwrap();
+        assert_eq!(events(&mut state), [("connected", a.id())]);
+
+        // Already connected, so a rescan does nothing.
+        state.rescan(&mut host, None).unwrap();
+        assert_eq!(events(&mut state), []);
+    }
+}
@@ -0,0 +1,477 @@
+//! Keep track of the set of connected devices.
+//!
+//! See [`DeviceMonitor`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    sync::{Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures_core::Stream;
+use log::debug;
+use once_cell::sync::OnceCell;
+
+use crate::{
+    hotplug::{HotplugEvent, HotplugWatch},
+    DeviceId, DeviceInfo, Error,
+};
+
+/// A live view of the connected devices, combining
+/// [`list_devices`][`crate::list_devices`] and
+/// [`watch_devices`][`crate::watch_devices`].
+///
+/// A `DeviceMonitor` starts watching for hotplug events before listing the
+/// connected devices, so that no device is missed, and reconciles the two so
+/// that each device is reported exactly once. It is a [`Stream`] of
+/// [`MonitorEvent`]s, starting with [`MonitorEvent::Initial`] and followed by
+/// [`MonitorEvent::Added`] and [`MonitorEvent::Removed`] as devices come and
+/// go. Keep polling it for [`devices`][`Self::devices`] to stay up to date.
+///
+/// ### Debouncing
+///
+/// Some devices briefly connect and disconnect, or re-enumerate, while
+/// starting up. With [`with_debounce`][`Self::with_debounce`], a newly
+/// connected device is only reported once it has stayed connected for the
+/// debounce window, and a device that disconnects within the window is never
+/// reported at all. Disconnections of reported devices are reported
+/// immediately.
+///
+/// ### Example
+///
+/// ```no_run
+/// use futures_lite::stream::StreamExt;
+/// use nusb::monitor::{DeviceMonitor, MonitorEvent};
+/// # futures_lite::future::block_on(async {
+/// let mut monitor = DeviceMonitor::new().unwrap();
+///
+/// while let Some(event) = monitor.next().await {
+///     match event {
+///         MonitorEvent::Initial(devices) => println!("{} devices connected", devices.len()),
+///         MonitorEvent::Added(info) => println!("added: {info:?}"),
+///         MonitorEvent::Removed(info) => println!("removed: {info:?}"),
+///     }
+/// }
+/// # })
+/// ```
+pub struct DeviceMonitor {
+    watch: HotplugWatch,
+    initial: Option<Vec<DeviceInfo>>,
+    state: State,
+    timer: Timer,
+}
+
+/// The devices reported so far and those waiting to be reported, separate
+/// from the watch so that it can be driven with explicit times.
+struct State {
+    devices: HashMap<DeviceId, DeviceInfo>,
+    debounce: Option<Debounce>,
+}
+
+struct Debounce {
+    window: Duration,
+
+    /// Connected devices not yet reported, in order of their deadline.
+    waiting: VecDeque<(Instant, DeviceInfo)>,
+}
+
+impl DeviceMonitor {
+    /// Start monitoring devices, reporting changes as soon as they happen.
+    pub fn new() -> Result<DeviceMonitor, Error> {
+        DeviceMonitor::start(None)
+    }
+
+    /// Start monitoring devices, reporting connected devices only after they
+    /// have stayed connected for `window`.
+    ///
+    /// Devices that are already connected are reported immediately.
+    pub fn with_debounce(window: Duration) -> Result<DeviceMonitor, Error> {
+        DeviceMonitor::start(Some(Debounce {
+            window,
+            waiting: VecDeque::new(),
+        }))
+    }
+
+    fn start(debounce: Option<Debounce>) -> Result<DeviceMonitor, Error> {
+        // Start watching before listing devices so a newly-attached device
+        // can't be missed.
+        let watch = crate::platform::HotplugWatch::new()?;
+        let initial: Vec<DeviceInfo> = crate::platform::list_devices()?.collect();
+        let watch = HotplugWatch::new(watch, initial.iter().cloned());
+        let devices = initial.iter().map(|d| (d.id(), d.clone())).collect();
+
+        Ok(DeviceMonitor {
+            watch,
+            initial: Some(initial),
+            state: State { devices, debounce },
+            timer: Timer::new(),
+        })
+    }
+
+    /// The currently connected devices.
+    ///
+    /// This reflects the events that have been returned from the stream so
+    /// far, and does not include devices still within the debounce window.
+    pub fn devices(&self) -> &HashMap<DeviceId, DeviceInfo> {
+        &self.state.devices
+    }
+
+    /// Information about the connected device with the specified ID, or
+    /// `None` if it is not connected.
+    pub fn get(&self, id: DeviceId) -> Option<&DeviceInfo> {
+        self.state.devices.get(&id)
+    }
+}
+
+impl State {
+    fn handle_connected(&mut self, info: DeviceInfo, now: Instant) -> Option<MonitorEvent> {
+        if let Some(d) = self.devices.get_mut(&info.id()) {
+            // Already reported, e.g. from `list_devices` after the watch
+            // started, so just refresh the information.
+            *d = info;
+            return None;
+        }
+
+        match &mut self.debounce {
+            Some(debounce) => {
+                match debounce
+                    .waiting
+                    .iter_mut()
+                    .find(|(_, d)| d.id() == info.id())
+                {
+                    Some((_, d)) => *d = info,
+                    None => {
+                        debug!("Device {:?} connected, waiting for debounce", info.id());
+                        debounce.waiting.push_back((now + debounce.window, info));
+                    }
+                }
+                None
+            }
+            None => {
+                self.devices.insert(info.id(), info.clone());
+                Some(MonitorEvent::Added(info))
+            }
+        }
+    }
+
+    fn handle_disconnected(&mut self, id: DeviceId) -> Option<MonitorEvent> {
+        if let Some(debounce) = &mut self.debounce {
+            if let Some(pos) = debounce.waiting.iter().position(|(_, d)| d.id() == id) {
+                debug!("Device {id:?} disconnected within debounce window");
+                debounce.waiting.remove(pos);
+                return None;
+            }
+        }
+
+        self.devices.remove(&id).map(MonitorEvent::Removed)
+    }
+
+    /// Report the first device whose debounce window has ended by `now`.
+    fn handle_expired(&mut self, now: Instant) -> Option<MonitorEvent> {
+        let debounce = self.debounce.as_mut()?;
+        if debounce.waiting.front()?.0 > now {
+            return None;
+        }
+
+        let (_, info) = debounce.waiting.pop_front()?;
+        self.devices.insert(info.id(), info.clone());
+        Some(MonitorEvent::Added(info))
+    }
+
+    /// When the next debounce window ends, if any device is waiting.
+    fn next_deadline(&self) -> Option<Instant> {
+        Some(self.debounce.as_ref()?.waiting.front()?.0)
+    }
+}
+
+impl Stream for DeviceMonitor {
+    type Item = MonitorEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(initial) = this.initial.take() {
+            return Poll::Ready(Some(MonitorEvent::Initial(initial)));
+        }
+
+        while let Poll::Ready(event) = this.watch.poll_event(cx) {
+            let event = match event {
+                HotplugEvent::Connected(info) => this.state.handle_connected(info, Instant::now()),
+                HotplugEvent::Disconnected(id, _) => this.state.handle_disconnected(id),
+                _ => None,
+            };
+
+            if let Some(event) = event {
+                return Poll::Ready(Some(event));
+            }
+        }
+
+        if let Some(event) = this.state.handle_expired(Instant::now()) {
+            return Poll::Ready(Some(event));
+        }
+
+        match this.state.next_deadline() {
+            Some(deadline) => this.timer.set(deadline, cx.waker()),
+            None => this.timer.cancel(),
+        }
+        Poll::Pending
+    }
+}
+
+/// Event returned from the [`DeviceMonitor`] stream.
+#[derive(Debug)]
+pub enum MonitorEvent {
+    /// The devices that were connected when the monitor was created.
+    ///
+    /// This is always the first event, and is returned exactly once.
+    Initial(Vec<DeviceInfo>),
+
+    /// A device was connected.
+    Added(DeviceInfo),
+
+    /// A previously reported device was disconnected.
+    Removed(DeviceInfo),
+}
+
+/// Wakes a task at a deadline.
+///
+/// Deadlines are checked by the stream itself when it is polled; the timer
+/// only wakes the task so that it is polled again. All timers share a single
+/// thread, started on first use, that sleeps until the earliest deadline.
+/// Only one deadline is pending per `Timer`; setting a new one replaces it.
+struct Timer {
+    id: usize,
+}
+
+struct Timers {
+    state: Mutex<TimersState>,
+    condvar: Condvar,
+}
+
+struct TimersState {
+    next_id: usize,
+    deadlines: HashMap<usize, (Instant, Waker)>,
+}
+
+static TIMERS: OnceCell<Timers> = OnceCell::new();
+
+fn timers() -> &'static Timers {
+    TIMERS.get_or_init(|| {
+        thread::spawn(timer_thread);
+        Timers {
+            state: Mutex::new(TimersState {
+                next_id: 0,
+                deadlines: HashMap::new(),
+            }),
+            condvar: Condvar::new(),
+        }
+    })
+}
+
+fn timer_thread() {
+    let timers = timers();
+    let mut state = timers.state.lock().unwrap();
+    loop {
+        let now = Instant::now();
+        state.deadlines.retain(|_, (deadline, waker)| {
+            if *deadline <= now {
+                waker.wake_by_ref();
+                false
+            } else {
+                true
+            }
+        });
+
+        state = match state.deadlines.values().map(|(d, _)| *d).min() {
+            Some(deadline) => {
+                timers
+                    .condvar
+                    .wait_timeout(state, deadline - now)
+                    .unwrap()
+                    .0
+            }
+            None => timers.condvar.wait(state).unwrap(),
+        };
+    }
+}
+
+impl Timer {
+    fn new() -> Timer {
+        let mut state = timers().state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        Timer { id }
+    }
+
+    fn set(&self, deadline: Instant, waker: &Waker) {
+        let timers = timers();
+        let mut state = timers.state.lock().unwrap();
+        match state.deadlines.get_mut(&self.id) {
+            Some((d, w)) if *d == deadline => w.clone_from(waker),
+            _ => {
+                state.deadlines.insert(self.id, (deadline, waker.clone()));
+                timers.condvar.notify_one();
+            }
+        }
+    }
+
+    fn cancel(&self) {
+        timers().state.lock().unwrap().deadlines.remove(&self.id);
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::{Wake, Waker},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::Timer;
+
+    struct Flag(AtomicBool);
+
+    impl Wake for Flag {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_timer() {
+        let timer = Timer::new();
+        let flag = Arc::new(Flag(AtomicBool::new(false)));
+        let cancelled = Timer::new();
+        let cancelled_flag = Arc::new(Flag(AtomicBool::new(false)));
+
+        timer.set(
+            Instant::now() + Duration::from_millis(50),
+            &Waker::from(flag.clone()),
+        );
+        cancelled.set(
+            Instant::now() + Duration::from_millis(50),
+            &Waker::from(cancelled_flag.clone()),
+        );
+        cancelled.cancel();
+        assert!(!flag.0.load(Ordering::SeqCst));
+
+        thread::sleep(Duration::from_millis(500));
+        assert!(flag.0.load(Ordering::SeqCst));
+        assert!(!cancelled_flag.0.load(Ordering::SeqCst));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    mod state {
+        use std::{
+            collections::VecDeque,
+            time::{Duration, Instant},
+        };
+
+        use super::super::{Debounce, MonitorEvent, State};
+        use crate::{platform::device_info, DeviceInfo};
+
+        fn state(debounce: Option<Duration>, connected: &[DeviceInfo]) -> State {
+            State {
+                devices: connected.iter().map(|d| (d.id(), d.clone())).collect(),
+                debounce: debounce.map(|window| Debounce {
+                    window,
+                    waiting: VecDeque::new(),
+                }),
+            }
+        }
+
+        fn added(event: Option<MonitorEvent>) -> DeviceInfo {
+            match event {
+                Some(MonitorEvent::Added(info)) => info,
+                other => panic!("expected Added, got {other:?}"),
+            }
+        }
+
+        #[test]
+        fn test_without_debounce() {
+            let now = Instant::now();
+            let existing = device_info(1, 2, 0x1209, 1);
+            let new = device_info(1, 3, 0x1209, 2);
+            let mut state = state(None, std::slice::from_ref(&existing));
+
+            // Devices already listed are refreshed, not reported again.
+            assert!(state.handle_connected(existing.clone(), now).is_none());
+            assert_eq!(
+                added(state.handle_connected(new.clone(), now)).id(),
+                new.id()
+            );
+            assert_eq!(state.devices.len(), 2);
+            assert_eq!(state.next_deadline(), None);
+
+            assert!(matches!(
+                state.handle_disconnected(new.id()),
+                Some(MonitorEvent::Removed(d)) if d.id() == new.id()
+            ));
+            assert!(state.handle_disconnected(new.id()).is_none());
+            assert_eq!(state.devices.keys().collect::<Vec<_>>(), [&existing.id()]);
+        }
+
+        #[test]
+        fn test_debounce() {
+            let window = Duration::from_millis(100);
+            let t0 = Instant::now();
+            let a = device_info(1, 2, 0x1209, 1);
+            let b = device_info(1, 3, 0x1209, 2);
+            let mut state = state(Some(window), &[]);
+
+            assert!(state.handle_connected(a.clone(), t0).is_none());
+            assert!(state
+                .handle_connected(b.clone(), t0 + Duration::from_millis(50))
+                .is_none());
+            assert_eq!(state.next_deadline(), Some(t0 + window));
+            assert!(state
+                .handle_expired(t0 + Duration::from_millis(99))
+                .is_none());
+            assert!(state.devices.is_empty());
+
+            // `b` disconnects within its window and is never reported.
+            assert!(state.handle_disconnected(b.id()).is_none());
+
+            assert_eq!(added(state.handle_expired(t0 + window)).id(), a.id());
+            assert!(state.handle_expired(t0 + window * 2).is_none());
+            assert_eq!(state.next_deadline(), None);
+            assert!(state.devices.contains_key(&a.id()));
+
+            // Disconnection of a reported device is immediate.
+            assert!(matches!(
+                state.handle_disconnected(a.id()),
+                Some(MonitorEvent::Removed(_))
+            ));
+        }
+
+        #[test]
+        fn test_debounce_reconnect() {
+            let window = Duration::from_millis(100);
+            let t0 = Instant::now();
+            let a = device_info(1, 2, 0x1209, 1);
+            let mut state = state(Some(window), &[]);
+
+            // A connection while waiting keeps the original deadline.
+            assert!(state.handle_connected(a.clone(), t0).is_none());
+            assert!(state
+                .handle_connected(a.clone(), t0 + Duration::from_millis(50))
+                .is_none());
+            assert_eq!(state.next_deadline(), Some(t0 + window));
+            assert_eq!(added(state.handle_expired(t0 + window)).id(), a.id());
+        }
+    }
+}
@@ -21,6 +21,7 @@ use rustix::{
 };
 
 use super::{
+    enumeration::{DEVFS_USB_PREFIX, SYSFS_USB_DRIVERS},
     errno_to_transfer_error, events,
     usbfs::{self, Urb},
     SysfsPath,
@@ -51,13 +52,20 @@ impl LinuxDevice {
     pub(crate) fn from_device_info(d: &DeviceInfo) -> Result<Arc<LinuxDevice>, Error> {
         let busnum = d.busnum();
         let devnum = d.device_address();
-        let active_config = d.path.read_attr("bConfigurationValue")?;
 
-        let path = PathBuf::from(format!("/dev/bus/usb/{busnum:03}/{devnum:03}"));
+        // Devices enumerated from usbfs rather than sysfs have an empty path,
+        // in which case the active configuration is requested from the device.
+        let sysfs = Some(d.path.clone()).filter(|p| !p.0.as_os_str().is_empty());
+        let active_config = match &sysfs {
+            Some(path) => Some(path.read_attr("bConfigurationValue")?),
+            None => None,
+        };
+
+        let path = PathBuf::from(format!("{DEVFS_USB_PREFIX}/{busnum:03}/{devnum:03}"));
         let fd = rustix::fs::open(&path, OFlags::RDWR | OFlags::CLOEXEC, Mode::empty())
             .inspect_err(|e| warn!("Failed to open device {path:?}: {e}"))?;
 
-        let inner = Self::create_inner(fd, Some(d.path.clone()), Some(active_config));
+        let inner = Self::create_inner(fd, sysfs, active_config);
         if inner.is_ok() {
             debug!("Opened device bus={busnum} addr={devnum}",);
         }
@@ -306,6 +314,63 @@ impl LinuxDevice {
         usbfs::attach_kernel_driver(&self.fd, interface_number).map_err(|e| e.into())
     }
 
+    pub(crate) fn kernel_driver(&self, interface_number: u8) -> Result<Option<String>, Error> {
+        match usbfs::get_driver(&self.fd, interface_number) {
+            Ok(name) => Ok(Some(String::from_utf8_lossy(&name).into_owned())),
+            Err(Errno::NODATA) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub(crate) fn unbind_kernel_driver(
+        &self,
+        interface_number: u8,
+        driver: &str,
+    ) -> Result<(), Error> {
+        validate_driver_name(driver)?;
+
+        // Detaching only if the driver matches is done by the kernel, so that
+        // a different driver bound in the meantime is left alone. This claims
+        // the interface, which is then released to leave it unbound.
+        match usbfs::detach_driver_and_claim_interface(&self.fd, interface_number, driver) {
+            Ok(()) => {}
+            Err(Errno::BUSY) => {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("interface {interface_number} is not bound to {driver:?}"),
+                ));
+            }
+            Err(e) => return Err(e.into()),
+        }
+        usbfs::release_interface(&self.fd, interface_number)?;
+        debug!("Unbound driver {driver} from interface {interface_number}");
+        Ok(())
+    }
+
+    pub(crate) fn bind_kernel_driver(
+        &self,
+        interface_number: u8,
+        driver: &str,
+    ) -> Result<(), Error> {
+        validate_driver_name(driver)?;
+
+        let Some(sysfs) = self.sysfs.as_ref() else {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "binding a driver requires sysfs",
+            ));
+        };
+
+        let config = self.active_configuration_value();
+        let interface_name = sysfs.interface_name(config, interface_number);
+
+        let path = PathBuf::from(format!("{SYSFS_USB_DRIVERS}/{driver}/bind"));
+        std::fs::write(&path, &interface_name)
+            .inspect_err(|e| warn!("Failed to bind {interface_name} via {path:?}: {e}"))?;
+        debug!("Bound driver {driver} to interface {interface_name}");
+        Ok(())
+    }
+
     pub(crate) unsafe fn submit_urb(&self, urb: *mut Urb) {
         let ep = unsafe { (*urb).endpoint };
         if let Err(e) = usbfs::submit_urb(&self.fd, urb) {
@@ -407,6 +472,18 @@ impl LinuxDevice {
     }
 }
 
+/// Check that `driver` is a plain driver name, so that it can't refer to a
+/// path outside of its directory in `/sys/bus/usb/drivers`.
+fn validate_driver_name(driver: &str) -> Result<(), Error> {
+    if driver.is_empty() || driver == "." || driver.contains(['/', '\0']) || driver.contains("..") {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid driver name {driver:?}"),
+        ));
+    }
+    Ok(())
+}
+
 impl Drop for LinuxDevice {
     fn drop(&mut self) {
         debug!("Closing device {}", self.events_id);
@@ -487,3 +564,30 @@ impl Drop for LinuxInterface {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::validate_driver_name;
+
+    #[test]
+    fn test_validate_driver_name() {
+        for name in ["usbhid", "cdc_acm", "usb-storage", "r8152"] {
+            assert!(validate_driver_name(name).is_ok(), "{name}");
+        }
+
+        for name in [
+            "",
+            ".",
+            "..",
+            "../../class",
+            "usbhid/bind",
+            "/dev/null",
+            "a\0b",
+        ] {
+            let err = validate_driver_name(name).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput, "{name:?}");
+        }
+    }
+}
@@ -1,16 +1,26 @@
 use std::fs;
 use std::io;
+use std::io::Read;
 use std::num::ParseIntError;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use log::debug;
 use log::warn;
+use rustix::fs::{Mode, OFlags};
 
-use crate::enumeration::InterfaceInfo;
-use crate::{BusInfo, DeviceInfo, Error, Speed, UsbControllerType};
+use super::usbfs;
+use crate::descriptors::{
+    parse_concatenated_config_descriptors, Configuration, DESCRIPTOR_LEN_DEVICE,
+    DESCRIPTOR_TYPE_DEVICE,
+};
 
-#[derive(Debug, Clone)]
+use crate::enumeration::{EndpointInfo, InterfaceInfo, PortConnectType, PortInfo};
+use crate::{BusInfo, DeviceId, DeviceInfo, Error, Speed, UsbControllerType};
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct SysfsPath(pub(crate) PathBuf);
 
 #[derive(Debug)]
@@ -48,7 +58,7 @@ impl From<SysfsError> for io::Error {
 }
 
 impl SysfsPath {
-    fn parse_attr<T, E>(
+    pub(crate) fn parse_attr<T, E>(
         &self,
         attr: &str,
         parse: impl FnOnce(&str) -> Result<T, E>,
@@ -69,7 +79,38 @@ impl SysfsPath {
         self.parse_attr(attr, |s| s.parse())
     }
 
-    fn read_attr_hex<T: FromHexStr>(&self, attr: &str) -> Result<T, SysfsError> {
+    pub(crate) fn write_attr(&self, attr: &str, value: &str) -> Result<(), io::Error> {
+        let attr_path = self.0.join(attr);
+        fs::write(&attr_path, value).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!(
+                    "failed to write sysfs attribute {}: {e}",
+                    attr_path.display()
+                ),
+            )
+        })
+    }
+
+    /// Name of an interface of the device, like `1-6:1.0`, or `1-0:1.0` for
+    /// the root hub `usb1`.
+    pub(crate) fn interface_name(&self, config: u8, interface: u8) -> String {
+        let name = self.0.file_name().unwrap_or_default().to_string_lossy();
+        match name.strip_prefix("usb") {
+            Some(busnum) => format!("{busnum}-0:{config}.{interface}"),
+            None => format!("{name}:{config}.{interface}"),
+        }
+    }
+
+    /// Path of an interface of the device's active configuration.
+    pub(crate) fn interface(&self, interface: u8) -> Result<SysfsPath, SysfsError> {
+        let config: u8 = self.read_attr("bConfigurationValue")?;
+        Ok(SysfsPath(
+            self.0.join(self.interface_name(config, interface)),
+        ))
+    }
+
+    pub(crate) fn read_attr_hex<T: FromHexStr>(&self, attr: &str) -> Result<T, SysfsError> {
         self.parse_attr(attr, |s| T::from_hex_str(s.strip_prefix("0x").unwrap_or(s)))
     }
 
@@ -101,10 +142,16 @@ impl SysfsPath {
     }
 }
 
-trait FromHexStr: Sized {
+pub(crate) trait FromHexStr: Sized {
     fn from_hex_str(s: &str) -> Result<Self, ParseIntError>;
 }
 
+impl FromHexStr for u32 {
+    fn from_hex_str(s: &str) -> Result<Self, ParseIntError> {
+        u32::from_str_radix(s, 16)
+    }
+}
+
 impl FromHexStr for u8 {
     fn from_hex_str(s: &str) -> Result<Self, ParseIntError> {
         u8::from_str_radix(s, 16)
@@ -117,70 +164,433 @@ impl FromHexStr for u16 {
     }
 }
 
-const SYSFS_USB_PREFIX: &'static str = "/sys/bus/usb/devices/";
+const SYSFS_ROOT: &str = "/sys";
+const DEVFS_ROOT: &str = "/dev";
 
-pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
-    Ok(fs::read_dir(SYSFS_USB_PREFIX)?.flat_map(|entry| {
-        let path = entry.ok()?.path();
-        let name = path.file_name()?;
-
-        // Device names look like `1-6` or `1-6.4.2`
-        // We'll ignore:
-        //  * root hubs (`usb1`) -- they're not useful to talk to and are not exposed on other platforms
-        //  * interfaces (`1-6:1.0`)
-        if !name
-            .as_encoded_bytes()
-            .iter()
-            .all(|c| matches!(c, b'0'..=b'9' | b'-' | b'.'))
-        {
-            return None;
-        }
+pub(crate) const DEVFS_USB_PREFIX: &str = "/dev/bus/usb";
+pub(crate) const SYSFS_USB_DRIVERS: &str = "/sys/bus/usb/drivers";
 
-        probe_device(SysfsPath(path))
-            .inspect_err(|e| warn!("{e}; ignoring device"))
-            .ok()
-    }))
+/// *(Linux-only)* Builder for listing devices and buses from a custom sysfs
+/// and devfs root.
+///
+/// [`list_devices`][`crate::list_devices`] and
+/// [`list_buses`][`crate::list_buses`] read `/sys/bus/usb/devices`, or
+/// `/dev/bus/usb` if sysfs is unavailable. An `Enumerator` reads the same
+/// paths under different roots, for example a sysfs tree captured from
+/// another system for tests, or a sysfs mounted elsewhere in a container.
+///
+/// Devices are always opened from `/dev/bus/usb`, regardless of the root
+/// they were listed from.
+///
+/// ### Example
+///
+/// ```no_run
+/// let devices = nusb::Enumerator::new()
+///     .sysfs_root("/mnt/host/sys")
+///     .list_devices()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Enumerator {
+    sysfs_root: PathBuf,
+    devfs_root: PathBuf,
+}
+
+impl Default for Enumerator {
+    fn default() -> Self {
+        Enumerator {
+            sysfs_root: PathBuf::from(SYSFS_ROOT),
+            devfs_root: PathBuf::from(DEVFS_ROOT),
+        }
+    }
 }
 
-pub fn list_root_hubs() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
-    Ok(fs::read_dir(SYSFS_USB_PREFIX)?.filter_map(|entry| {
-        let path = entry.ok()?.path();
-        let name = path.file_name()?;
+impl Enumerator {
+    /// Create an `Enumerator` using the default roots, `/sys` and `/dev`.
+    pub fn new() -> Enumerator {
+        Enumerator::default()
+    }
+
+    /// Set the directory where sysfs is mounted, instead of `/sys`.
+    pub fn sysfs_root(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sysfs_root = path.into();
+        self
+    }
+
+    /// Set the directory where devfs is mounted, instead of `/dev`.
+    pub fn devfs_root(mut self, path: impl Into<PathBuf>) -> Self {
+        self.devfs_root = path.into();
+        self
+    }
+
+    fn sysfs_usb_devices(&self) -> PathBuf {
+        self.sysfs_root.join("bus/usb/devices")
+    }
+
+    pub(super) fn devfs_usb(&self) -> PathBuf {
+        self.devfs_root.join("bus/usb")
+    }
+
+    /// Probe the device with the usbfs device node `BBB/DDD`, from sysfs if
+    /// available or otherwise from the node itself.
+    pub(super) fn probe_usbfs_device(&self, busnum: u8, devnum: u8) -> Result<DeviceInfo, Error> {
+        let minor = device_node_minor(busnum, devnum).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "invalid bus or device number")
+        })?;
+        let char_path = self.sysfs_root.join(format!("dev/char/189:{minor}"));
 
-        // root hubs are named `usbX` where X is the bus number
-        if !name.to_string_lossy().starts_with("usb") {
-            return None;
+        match char_path.canonicalize() {
+            Ok(path) => Ok(probe_device(SysfsPath(path))?),
+            Err(e) => {
+                debug!("Failed to find sysfs path for device {busnum:03}/{devnum:03}: {e}");
+                let path = self.devfs_usb().join(format!("{busnum:03}/{devnum:03}"));
+                probe_device_node(&path, busnum, devnum)
+            }
         }
+    }
 
-        probe_device(SysfsPath(path))
-            .inspect_err(|e| warn!("{e}; ignoring root hub"))
-            .ok()
-    }))
+    /// Get an iterator listing the connected devices.
+    ///
+    /// See [`list_devices`][`crate::list_devices`].
+    pub fn list_devices(&self) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+        // Without sysfs, e.g. in a container with only `/dev/bus/usb` bind-mounted,
+        // fall back to reading what we can from the usbfs device nodes.
+        let sysfs_dir = self.sysfs_usb_devices();
+        let (sysfs, usbfs) = match fs::read_dir(&sysfs_dir) {
+            Ok(dir) => (Some(dir), Vec::new()),
+            Err(sysfs_err) => match self.list_devices_usbfs() {
+                Ok(devices) => {
+                    debug!(
+                        "Failed to read {sysfs_dir:?} ({sysfs_err}), enumerated {:?} instead",
+                        self.devfs_usb()
+                    );
+                    (None, devices)
+                }
+                Err(_) => return Err(sysfs_err),
+            },
+        };
+
+        Ok(sysfs
+            .into_iter()
+            .flatten()
+            .flat_map(|entry| {
+                let path = entry.ok()?.path();
+                let name = path.file_name()?;
+
+                // Device names look like `1-6` or `1-6.4.2`
+                // We'll ignore:
+                //  * root hubs (`usb1`) -- they're not useful to talk to and are not exposed on other platforms
+                //  * interfaces (`1-6:1.0`)
+                if !name
+                    .as_encoded_bytes()
+                    .iter()
+                    .all(|c| matches!(c, b'0'..=b'9' | b'-' | b'.'))
+                {
+                    return None;
+                }
+
+                probe_device(SysfsPath(path))
+                    .inspect_err(|e| warn!("{e}; ignoring device"))
+                    .ok()
+            })
+            .chain(usbfs))
+    }
+
+    fn list_devices_usbfs(&self) -> Result<Vec<DeviceInfo>, Error> {
+        let mut devices = Vec::new();
+
+        for bus in fs::read_dir(self.devfs_usb())? {
+            let bus = bus?;
+            let Some(Ok(busnum)) = bus.file_name().to_str().map(str::parse::<u8>) else {
+                continue;
+            };
+
+            for dev in fs::read_dir(bus.path())? {
+                let dev = dev?;
+                let Some(Ok(devnum)) = dev.file_name().to_str().map(str::parse::<u8>) else {
+                    continue;
+                };
+
+                // Address 1 is always the root hub
+                if devnum == 1 {
+                    continue;
+                }
+
+                match probe_device_node(&dev.path(), busnum, devnum) {
+                    Ok(device) => devices.push(device),
+                    Err(e) => {
+                        warn!(
+                            "Failed to probe device {:?}: {e}; ignoring device",
+                            dev.path()
+                        )
+                    }
+                }
+            }
+        }
+
+        Ok(devices)
+    }
+
+    /// Get an iterator listing the root hubs.
+    fn list_root_hubs(&self) -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+        Ok(fs::read_dir(self.sysfs_usb_devices())?.filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?;
+
+            // root hubs are named `usbX` where X is the bus number
+            if !name.to_string_lossy().starts_with("usb") {
+                return None;
+            }
+
+            probe_device(SysfsPath(path))
+                .inspect_err(|e| warn!("{e}; ignoring root hub"))
+                .ok()
+        }))
+    }
+
+    /// Get an iterator listing the system USB buses.
+    ///
+    /// See [`list_buses`][`crate::list_buses`].
+    pub fn list_buses(&self) -> Result<impl Iterator<Item = BusInfo>, Error> {
+        let mut buses: Vec<BusInfo> = self
+            .list_root_hubs()?
+            .filter_map(|rh| {
+                // get the parent by following the absolute symlink; root hub in /bus/usb is a symlink to a dir in parent bus
+                let parent_path = rh
+                    .path
+                    .0
+                    .canonicalize()
+                    .ok()
+                    .and_then(|p| p.parent().map(|p| SysfsPath(p.to_owned())))?;
+
+                debug!("Probing parent device {:?}", parent_path.0);
+                let driver = parent_path.readlink_attr_filename("driver").ok();
+
+                Some(BusInfo {
+                    bus_id: rh.bus_id.to_owned(),
+                    path: rh.path.to_owned(),
+                    busnum: rh.busnum,
+                    controller_type: driver.as_ref().and_then(|p| UsbControllerType::from_str(p)),
+                    driver,
+                    pci_vendor_id: parent_path.read_attr_hex("vendor").ok(),
+                    pci_device_id: parent_path.read_attr_hex("device").ok(),
+                    num_ports: rh.path.read_attr("maxchild").ok(),
+                    companion_busnum: None,
+                    parent_path,
+                    root_hub: rh,
+                })
+            })
+            .collect();
+
+        // xHCI controllers have separate USB 2 and USB 3 buses
+        for i in 0..buses.len() {
+            buses[i].companion_busnum = buses
+                .iter()
+                .find(|b| b.busnum != buses[i].busnum && b.parent_path.0 == buses[i].parent_path.0)
+                .map(|b| b.busnum);
+        }
+
+        Ok(buses.into_iter())
+    }
+
+    /// List the kernel drivers currently bound to devices and interfaces, as
+    /// `(device, interface number, driver)`.
+    pub(crate) fn list_bound_drivers(
+        &self,
+    ) -> Result<impl Iterator<Item = (DeviceId, Option<u8>, String)>, Error> {
+        Ok(fs::read_dir(self.sysfs_usb_devices())?.filter_map(|entry| {
+            let path = SysfsPath(entry.ok()?.path());
+            let name = path.0.file_name()?.to_str()?;
+
+            // Interfaces are named `1-6:1.0`, after their device `1-6`
+            let (device, interface) = match name.split_once(':') {
+                Some((device_name, _)) => (
+                    SysfsPath(path.0.with_file_name(device_name)),
+                    Some(interface_number_from_name(name)?),
+                ),
+                None => (path.clone(), None),
+            };
+
+            let id = DeviceId(super::DeviceId {
+                bus: device.read_attr("busnum").ok()?,
+                addr: device.read_attr("devnum").ok()?,
+            });
+            let driver = path.readlink_attr_filename("driver").ok()?;
+            Some((id, interface, driver))
+        }))
+    }
+
+    /// List the sysfs names of devices, such as `1-6`, with their IDs.
+    pub(crate) fn list_device_names(
+        &self,
+    ) -> Result<impl Iterator<Item = (String, super::DeviceId)>, Error> {
+        Ok(fs::read_dir(self.sysfs_usb_devices())?.filter_map(|entry| {
+            let path = SysfsPath(entry.ok()?.path());
+            let name = path.0.file_name()?.to_str()?.to_owned();
+            if name.contains(':') {
+                return None;
+            }
+
+            let id = super::DeviceId {
+                bus: path.read_attr("busnum").ok()?,
+                addr: path.read_attr("devnum").ok()?,
+            };
+            Some((name, id))
+        }))
+    }
+}
+
+pub fn list_devices() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
+    Enumerator::new().list_devices()
 }
 
 pub fn list_buses() -> Result<impl Iterator<Item = BusInfo>, Error> {
-    Ok(list_root_hubs()?.filter_map(|rh| {
-        // get the parent by following the absolute symlink; root hub in /bus/usb is a symlink to a dir in parent bus
-        let parent_path = rh
-            .path
-            .0
-            .canonicalize()
-            .ok()
-            .and_then(|p| p.parent().map(|p| SysfsPath(p.to_owned())))?;
-
-        debug!("Probing parent device {:?}", parent_path.0);
-        let driver = parent_path.readlink_attr_filename("driver").ok();
-
-        Some(BusInfo {
-            bus_id: rh.bus_id.to_owned(),
-            path: rh.path.to_owned(),
-            parent_path: parent_path.to_owned(),
-            busnum: rh.busnum,
-            controller_type: driver.as_ref().and_then(|p| UsbControllerType::from_str(p)),
-            driver,
-            root_hub: rh,
-        })
-    }))
+    Enumerator::new().list_buses()
+}
+
+/// List the ports of a hub, which are found in its first interface.
+pub fn list_ports(device: &SysfsPath) -> Result<Vec<PortInfo>, Error> {
+    let interface = match device.interface(0) {
+        Ok(interface) => interface,
+        // Unconfigured
+        Err(SysfsError(_, SysfsErrorKind::Parse(_))) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut ports: Vec<_> = interface.children().filter_map(probe_port).collect();
+    ports.sort_by_key(|p| p.port_number);
+    Ok(ports)
+}
+
+/// Probe a port directory, named like `usb1-port2` or `1-6-port2`.
+pub fn probe_port(path: SysfsPath) -> Option<PortInfo> {
+    let name = path.0.file_name()?.to_str()?;
+    let (_, port_number) = name.rsplit_once("-port")?;
+    let port_number = port_number.parse().ok()?;
+
+    Some(PortInfo {
+        port_number,
+        connect_type: path
+            .read_attr::<String>("connect_type")
+            .map(|s| PortConnectType::from_str(&s))
+            .unwrap_or(PortConnectType::Unknown),
+        location: path.read_attr_hex("location").ok(),
+        path,
+    })
+}
+
+/// Check that `attr` names an attribute directly in a sysfs directory, so
+/// that a name from the caller can't refer to a file elsewhere.
+pub(crate) fn check_attr_name(attr: &str) -> Result<(), Error> {
+    if attr.is_empty() || attr == "." || attr == ".." || attr.contains(['/', '\0']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid sysfs attribute name {attr:?}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Parse the interface number from a sysfs interface name like `1-6:1.0`.
+pub(crate) fn interface_number_from_name(name: &str) -> Option<u8> {
+    let (_, config_interface) = name.split_once(':')?;
+    let (_, interface) = config_interface.split_once('.')?;
+    interface.parse().ok()
+}
+
+/// The minor number of the usbfs device node (major 189) for a device.
+///
+/// Returns `None` for bus or device number 0, which the kernel never assigns.
+pub(crate) fn device_node_minor(busnum: u8, devnum: u8) -> Option<u32> {
+    let bus = u32::from(busnum).checked_sub(1)?;
+    let addr = u32::from(devnum).checked_sub(1)?;
+    Some(bus * 128 + addr)
+}
+
+/// Build a `DeviceInfo` from the descriptors and connection information
+/// available from a usbfs device node, for when sysfs is unavailable.
+///
+/// String descriptors aren't cached by usbfs, so strings are `None`, as are
+/// the port chain and speed on kernels without `USBDEVFS_CONNINFO_EX`. The
+/// interfaces are only known if the device has a single configuration.
+fn probe_device_node(path: &Path, busnum: u8, devnum: u8) -> Result<DeviceInfo, Error> {
+    debug!("Probing device {path:?}");
+
+    let fd = rustix::fs::open(path, OFlags::RDONLY | OFlags::CLOEXEC, Mode::empty())?;
+    let mut descriptors = Vec::new();
+    fs::File::from(fd.try_clone()?).read_to_end(&mut descriptors)?;
+
+    let device_descriptor = descriptors
+        .get(..DESCRIPTOR_LEN_DEVICE as usize)
+        .filter(|d| d[0] == DESCRIPTOR_LEN_DEVICE && d[1] == DESCRIPTOR_TYPE_DEVICE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid device descriptor"))?;
+
+    let conninfo = usbfs::get_conninfo_ex(&fd)
+        .inspect_err(|e| debug!("USBDEVFS_CONNINFO_EX failed for {path:?}: {e}"))
+        .ok();
+
+    let configurations: Vec<_> =
+        parse_concatenated_config_descriptors(&descriptors[DESCRIPTOR_LEN_DEVICE as usize..])
+            .map(Configuration::new)
+            .collect();
+
+    let interfaces = match &configurations[..] {
+        [config] => config
+            .interface_alt_settings()
+            .filter(|i| i.alternate_setting() == 0)
+            .map(|i| InterfaceInfo {
+                interface_number: i.interface_number(),
+                class: i.class(),
+                subclass: i.subclass(),
+                protocol: i.protocol(),
+                interface_string: None,
+                alt_setting: 0,
+                num_endpoints: i.num_endpoints(),
+                endpoints: i
+                    .endpoints()
+                    .map(|e| EndpointInfo {
+                        address: e.address(),
+                        attributes: e.attributes(),
+                        max_packet_size_raw: e.max_packet_size_raw(),
+                        interval: e.interval(),
+                    })
+                    .collect(),
+                driver: None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(DeviceInfo {
+        path: SysfsPath(PathBuf::new()),
+        busnum,
+        bus_id: format!("{busnum:03}"),
+        device_address: devnum,
+        port_chain: conninfo
+            .as_ref()
+            .map(|c| c.ports[..(c.num_ports as usize).min(c.ports.len())].to_vec())
+            .unwrap_or_default(),
+        vendor_id: u16::from_le_bytes([device_descriptor[8], device_descriptor[9]]),
+        product_id: u16::from_le_bytes([device_descriptor[10], device_descriptor[11]]),
+        device_version: u16::from_le_bytes([device_descriptor[12], device_descriptor[13]]),
+        class: device_descriptor[4],
+        subclass: device_descriptor[5],
+        protocol: device_descriptor[6],
+        max_packet_size_0: device_descriptor[7],
+        speed: conninfo.and_then(|c| match c.speed {
+            1 => Some(Speed::Low),
+            2 => Some(Speed::Full),
+            3 => Some(Speed::High),
+            5 => Some(Speed::Super),
+            6 => Some(Speed::SuperPlus),
+            _ => None,
+        }),
+        manufacturer_string: None,
+        product_string: None,
+        serial_number: None,
+        interfaces,
+        descriptors,
+    })
 }
 
 pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
@@ -202,6 +612,7 @@ pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
 
     Ok(DeviceInfo {
         busnum,
+        descriptors: fs::read(path.0.join("descriptors")).unwrap_or_default(),
         bus_id: format!("{busnum:03}"),
         device_address,
         port_chain,
@@ -233,12 +644,19 @@ pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
                         .contains(&b':')
                 })
                 .flat_map(|i| {
+                    let endpoints = probe_endpoints(&i);
                     Some(InterfaceInfo {
                         interface_number: i.read_attr_hex("bInterfaceNumber").ok()?,
                         class: i.read_attr_hex("bInterfaceClass").ok()?,
                         subclass: i.read_attr_hex("bInterfaceSubClass").ok()?,
                         protocol: i.read_attr_hex("bInterfaceProtocol").ok()?,
                         interface_string: i.read_attr("interface").ok(),
+                        alt_setting: i.read_attr("bAlternateSetting").unwrap_or(0),
+                        num_endpoints: i
+                            .read_attr_hex("bNumEndpoints")
+                            .unwrap_or(endpoints.len() as u8),
+                        endpoints,
+                        driver: i.readlink_attr_filename("driver").ok(),
                     })
                 })
                 .collect();
@@ -248,3 +666,565 @@ pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
         path,
     })
 }
+
+/// Read the `ep_XX` directories of an interface.
+fn probe_endpoints(interface: &SysfsPath) -> Vec<EndpointInfo> {
+    let mut endpoints: Vec<_> = interface
+        .children()
+        .filter(|e| {
+            e.0.file_name()
+                .unwrap_or_default()
+                .as_encoded_bytes()
+                .starts_with(b"ep_")
+        })
+        .flat_map(|e| {
+            Some(EndpointInfo {
+                address: e.read_attr_hex("bEndpointAddress").ok()?,
+                attributes: e.read_attr_hex("bmAttributes").ok()?,
+                max_packet_size_raw: e.read_attr_hex("wMaxPacketSize").ok()?,
+                interval: e.read_attr_hex("bInterval").ok()?,
+            })
+        })
+        .collect();
+    endpoints.sort_unstable_by_key(|e| e.address);
+    endpoints
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::{device_node_minor, Enumerator};
+    use crate::{
+        transfer::{Direction, EndpointType},
+        AuthorizedDefault, DeviceInfo, PortConnectType, Speed, UsbControllerType,
+    };
+
+    /// A sysfs and devfs tree materialized in a temporary directory from a
+    /// fixture file in `tests/fixtures/linux`, removed on drop.
+    ///
+    /// Fixtures are stored as text rather than as directory trees, because
+    /// sysfs names contain `:`, and symlinks, which can't be checked out on
+    /// all platforms.
+    pub(crate) struct Fixture(PathBuf);
+
+    impl Fixture {
+        pub(crate) fn load(name: &str) -> Fixture {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+            let manifest = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures/linux")
+                .join(name)
+                .with_extension("txt");
+            let manifest = fs::read_to_string(&manifest).unwrap();
+
+            let root = std::env::temp_dir().join(format!(
+                "nusb-fixture-{name}-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            let fixture = Fixture(root);
+
+            for line in manifest.lines() {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let create = |path: &str| {
+                    let path = fixture.0.join(path);
+                    fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    path
+                };
+
+                if let Some((path, target)) = line.split_once(" -> ") {
+                    std::os::unix::fs::symlink(target, create(path)).unwrap();
+                } else if let Some((path, bytes)) = line.split_once(" =hex ") {
+                    let bytes: Vec<u8> = bytes
+                        .split_whitespace()
+                        .map(|b| u8::from_str_radix(b, 16).unwrap())
+                        .collect();
+                    fs::write(create(path), bytes).unwrap();
+                } else if let Some((path, contents)) = line.split_once(" = ") {
+                    fs::write(create(path), format!("{contents}\n")).unwrap();
+                } else {
+                    panic!("invalid fixture line {line:?}");
+                }
+            }
+
+            fixture
+        }
+
+        /// The path of a file in the fixture, to modify it.
+        pub(crate) fn path(&self, path: &str) -> PathBuf {
+            self.0.join(path)
+        }
+
+        pub(crate) fn enumerator(&self) -> Enumerator {
+            Enumerator::new()
+                .sysfs_root(self.0.join("sys"))
+                .devfs_root(self.0.join("dev"))
+        }
+
+        pub(crate) fn list_devices(&self) -> Vec<DeviceInfo> {
+            let mut devices: Vec<_> = self.enumerator().list_devices().unwrap().collect();
+            devices.sort_by_key(|d| (d.busnum(), d.port_chain().to_vec()));
+            devices
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    /// A `DeviceInfo` with the specified bus number, address, and IDs, not
+    /// backed by sysfs, for tests of code that tracks devices.
+    pub(crate) fn device_info(busnum: u8, addr: u8, vendor_id: u16, product_id: u16) -> DeviceInfo {
+        DeviceInfo {
+            path: super::SysfsPath(PathBuf::new()),
+            busnum,
+            descriptors: Vec::new(),
+            bus_id: format!("{busnum:03}"),
+            device_address: addr,
+            port_chain: vec![addr],
+            vendor_id,
+            product_id,
+            device_version: 0,
+            class: 0,
+            subclass: 0,
+            protocol: 0,
+            max_packet_size_0: 64,
+            speed: None,
+            manufacturer_string: None,
+            product_string: None,
+            serial_number: None,
+            interfaces: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_hub() {
+        let fixture = Fixture::load("hub");
+        let devices = fixture.list_devices();
+        assert_eq!(devices.len(), 2);
+
+        let hub = &devices[0];
+        assert_eq!((hub.vendor_id(), hub.product_id()), (0x05e3, 0x0610));
+        assert_eq!(hub.class(), 0x09);
+        assert_eq!(hub.port_chain(), &[1]);
+        assert_eq!(hub.speed(), Some(Speed::High));
+        assert_eq!(hub.interfaces().count(), 1);
+
+        let mouse = &devices[1];
+        assert_eq!(mouse.bus_id(), "001");
+        assert_eq!(mouse.device_address(), 5);
+        assert_eq!(mouse.port_chain(), &[1, 2]);
+        assert_eq!(mouse.speed(), Some(Speed::Low));
+        assert_eq!(mouse.max_packet_size_0(), 8);
+        assert_eq!(mouse.manufacturer_string(), Some("Logitech"));
+        assert_eq!(mouse.serial_number(), None);
+        assert_eq!(
+            mouse.sysfs_path(),
+            fixture.0.join("sys/bus/usb/devices/1-1.2")
+        );
+
+        let buses: Vec<_> = fixture.enumerator().list_buses().unwrap().collect();
+        assert_eq!(buses.len(), 1);
+        assert_eq!(buses[0].busnum(), 1);
+        assert_eq!(buses[0].driver(), Some("xhci_hcd"));
+        assert_eq!(buses[0].controller_type(), Some(UsbControllerType::XHCI));
+        assert_eq!(buses[0].root_hub().vendor_id(), 0x1d6b);
+        assert_eq!(buses[0].num_ports(), Some(2));
+        assert_eq!(buses[0].companion_busnum(), None);
+        assert!(buses[0]
+            .parent_sysfs_path()
+            .ends_with("devices/pci0000:00/0000:00:14.0"));
+
+        assert_eq!(
+            buses[0].authorized_default().unwrap(),
+            AuthorizedDefault::All
+        );
+        buses[0]
+            .set_authorized_default(AuthorizedDefault::None)
+            .unwrap();
+        assert_eq!(
+            buses[0].authorized_default().unwrap(),
+            AuthorizedDefault::None
+        );
+        // Internal devices only, which is not the same as all devices.
+        buses[0].set_sysfs_attr("authorized_default", "2").unwrap();
+        assert_eq!(
+            buses[0].authorized_default().unwrap(),
+            AuthorizedDefault::Internal
+        );
+        buses[0]
+            .set_authorized_default(AuthorizedDefault::Wired)
+            .unwrap();
+        assert_eq!(buses[0].sysfs_attr::<i8>("authorized_default").unwrap(), -1);
+
+        assert!(mouse.authorized().unwrap());
+        mouse.set_authorized(false).unwrap();
+        assert!(!mouse.authorized().unwrap());
+
+        assert!(mouse.interface_authorized(0).unwrap());
+        mouse.set_interface_authorized(0, false).unwrap();
+        assert!(!mouse.interface_authorized(0).unwrap());
+        assert!(mouse.interface_authorized(1).is_err());
+
+        let ports = hub.ports().unwrap();
+        assert_eq!(
+            ports
+                .iter()
+                .map(|p| (p.port_number(), p.connect_type()))
+                .collect::<Vec<_>>(),
+            [
+                (1, PortConnectType::Hotplug),
+                (2, PortConnectType::Hotplug),
+                (3, PortConnectType::Hotplug),
+                (4, PortConnectType::NotUsed)
+            ]
+        );
+        assert_eq!(ports[1].location(), None);
+        assert!(ports[1].peer().is_none());
+        assert_eq!(ports[1].over_current_count().unwrap(), 0);
+        assert!(!ports[1].disabled().unwrap());
+        ports[1].set_disabled(true).unwrap();
+        assert!(ports[1].disabled().unwrap());
+        assert!(!ports[1].quarantined().unwrap());
+
+        assert!(mouse.ports().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_composite() {
+        let fixture = Fixture::load("composite");
+        let devices = fixture.list_devices();
+        assert_eq!(devices.len(), 1);
+
+        let device = &devices[0];
+        assert_eq!((device.vendor_id(), device.product_id()), (0x1209, 0x0010));
+        assert_eq!(
+            (device.class(), device.subclass(), device.protocol()),
+            (0xef, 0x02, 0x01)
+        );
+        assert_eq!(device.device_version(), 0x0100);
+        assert_eq!(device.speed(), Some(Speed::Full));
+        assert_eq!(device.serial_number(), Some("ABC123"));
+
+        let interfaces: Vec<_> = device
+            .interfaces()
+            .map(|i| (i.interface_number(), i.class(), i.interface_string()))
+            .collect();
+        assert_eq!(
+            interfaces,
+            [
+                (0, 0x02, Some("CDC ACM Control")),
+                (1, 0x0a, Some("CDC ACM Data")),
+                (2, 0x03, None)
+            ]
+        );
+
+        let data = device.interfaces().nth(1).unwrap();
+        assert_eq!(data.alt_setting(), 0);
+        assert_eq!(data.driver(), Some("cdc_acm"));
+        assert_eq!(data.num_endpoints(), 2);
+        let endpoints: Vec<_> = data
+            .endpoints()
+            .map(|e| {
+                (
+                    e.address(),
+                    e.direction(),
+                    e.transfer_type(),
+                    e.max_packet_size(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            endpoints,
+            [
+                (0x02, Direction::Out, EndpointType::Bulk, 64),
+                (0x81, Direction::In, EndpointType::Bulk, 64),
+            ]
+        );
+
+        let hid = device.interfaces().nth(2).unwrap();
+        assert_eq!(hid.driver(), None);
+        let ep = hid.endpoints().next().unwrap();
+        assert_eq!(ep.transfer_type(), EndpointType::Interrupt);
+        assert_eq!(ep.interval(), 10);
+
+        let desc = device.device_descriptor().unwrap();
+        assert_eq!((desc.vendor_id(), desc.product_id()), (0x1209, 0x0010));
+        assert_eq!(desc.serial_number_string_index(), Some(3));
+        assert_eq!(desc.num_configurations(), 1);
+
+        let configs: Vec<_> = device.configurations().collect();
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].configuration_value(), 1);
+        assert_eq!(configs[0].num_interfaces(), 3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let fixture = Fixture::load("composite");
+        let devices = fixture.list_devices();
+
+        let json = serde_json::to_value(&devices[0]).unwrap();
+        assert_eq!(json["vendor_id"], 0x1209);
+        assert_eq!(json["speed"], "full");
+        assert_eq!(json["interfaces"][1]["endpoints"][0]["address"], 0x02);
+
+        assert!(json["path"].as_str().unwrap().ends_with("1-2"));
+
+        // The sysfs path is not deserialized, so a deserialized device can't
+        // be pointed at other files.
+        let mut json = json;
+        json["path"] = "/tmp".into();
+        let device: DeviceInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(device.id(), devices[0].id());
+        assert_eq!(device.sysfs_path(), Path::new(""));
+        assert_eq!(device.configurations().count(), 1);
+        assert_eq!(
+            device.sysfs_attr::<String>("idVendor").unwrap_err().kind(),
+            std::io::ErrorKind::Unsupported
+        );
+        assert_eq!(
+            device.set_authorized(false).unwrap_err().kind(),
+            std::io::ErrorKind::Unsupported
+        );
+        let detached = |d: &DeviceInfo| format!("{d:?}").replace(&format!("{:?}", d.path), "");
+        assert_eq!(detached(&device), detached(&devices[0]));
+
+        let buses: Vec<_> = fixture.enumerator().list_buses().unwrap().collect();
+        let json = serde_json::to_string(&buses).unwrap();
+        let parsed: Vec<crate::BusInfo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0].busnum(), buses[0].busnum());
+        assert_eq!(parsed[0].sysfs_path(), Path::new(""));
+        assert_eq!(
+            parsed[0].authorized_default().unwrap_err().kind(),
+            std::io::ErrorKind::Unsupported
+        );
+        assert_eq!(
+            parsed[0]
+                .set_sysfs_attr("authorized_default", "1")
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::Unsupported
+        );
+
+        let speed: Speed = serde_json::from_str(r#""super+""#).unwrap();
+        assert_eq!(speed, Speed::SuperPlus);
+    }
+
+    #[test]
+    fn test_superspeed() {
+        let fixture = Fixture::load("superspeed");
+        let devices = fixture.list_devices();
+        assert_eq!(devices.len(), 1);
+
+        let device = &devices[0];
+        assert_eq!(device.bus_id(), "002");
+        assert_eq!(device.port_chain(), &[1]);
+        assert_eq!(device.speed(), Some(Speed::Super));
+        assert_eq!(device.max_packet_size_0(), 9);
+
+        assert_eq!(device.removable().unwrap(), Some(true));
+        assert_eq!(device.max_power_ma().unwrap(), 100);
+        assert_eq!(device.configuration_string().unwrap(), None);
+        assert!(device.ltm_capable().unwrap());
+        assert_eq!(device.quirks().unwrap(), 0);
+        assert_eq!(device.lanes().unwrap(), (1, 1));
+        assert_eq!(device.sysfs_attr::<String>("bMaxPower").unwrap(), "100mA");
+        assert!(device.sysfs_attr::<u8>("bMaxPower").is_err());
+        assert!(device.sysfs_attr::<String>("nonexistent").is_err());
+
+        assert!(!device.avoid_reset_quirk().unwrap());
+        device.set_avoid_reset_quirk(true).unwrap();
+        assert!(device.avoid_reset_quirk().unwrap());
+
+        // No `descriptors` file in this fixture
+        assert!(device.device_descriptor().is_none());
+        assert_eq!(device.configurations().count(), 0);
+        assert_eq!(
+            device.interfaces().map(|i| i.class()).collect::<Vec<_>>(),
+            [0x08]
+        );
+
+        let mut buses: Vec<_> = fixture.enumerator().list_buses().unwrap().collect();
+
+        buses.sort_by_key(|b| b.busnum());
+        assert_eq!(buses.len(), 2);
+        assert_eq!(buses[1].root_hub().speed(), Some(Speed::Super));
+
+        assert_eq!(buses[0].companion_busnum(), Some(2));
+        assert_eq!(buses[1].companion_busnum(), Some(1));
+        assert_eq!(buses[0].max_speed(), Some(Speed::High));
+        assert_eq!(buses[1].max_speed(), Some(Speed::Super));
+        assert_eq!(buses[1].num_ports(), Some(1));
+        assert_eq!(
+            (buses[1].pci_vendor_id(), buses[1].pci_device_id()),
+            (Some(0x8086), Some(0xa36d))
+        );
+
+        // The USB 2 and USB 3 root hub ports of the same connector are peers
+        let ports = buses[1].root_hub().ports().unwrap();
+        assert_eq!(ports.len(), 1);
+        assert_eq!(ports[0].location(), Some(0x80000001));
+        let peer = ports[0].peer().unwrap();
+        assert!(peer.sysfs_path().ends_with("usb1/1-0:1.0/usb1-port1"));
+        assert_eq!(peer.location(), Some(0x80000001));
+        assert!(peer
+            .peer()
+            .unwrap()
+            .sysfs_path()
+            .ends_with("usb2/2-0:1.0/usb2-port1"));
+
+        let ports = buses[0].root_hub().ports().unwrap();
+        assert_eq!(ports[1].connect_type(), PortConnectType::Hardwired);
+    }
+
+    #[test]
+    fn test_usbfs_fallback() {
+        let fixture = Fixture::load("usbfs");
+        let devices = fixture.list_devices();
+
+        // The root hub is skipped
+        assert_eq!(devices.len(), 1);
+
+        let device = &devices[0];
+        assert_eq!(device.bus_id(), "001");
+        assert_eq!(device.device_address(), 3);
+        assert_eq!((device.vendor_id(), device.product_id()), (0x1209, 0x0010));
+        assert_eq!(
+            (device.class(), device.subclass(), device.protocol()),
+            (0xef, 0x02, 0x01)
+        );
+        assert_eq!(device.max_packet_size_0(), 64);
+        assert_eq!(device.sysfs_path(), Path::new(""));
+        assert_eq!(
+            device.interface_authorized(0).unwrap_err().kind(),
+            std::io::ErrorKind::Unsupported
+        );
+
+        // Not available from a regular file
+        assert_eq!(device.speed(), None);
+        assert_eq!(device.port_chain(), &[] as &[u8]);
+        assert_eq!(device.product_string(), None);
+
+        let interfaces: Vec<_> = device
+            .interfaces()
+            .map(|i| (i.interface_number(), i.class()))
+            .collect();
+        assert_eq!(interfaces, [(0, 0x02), (1, 0x0a), (2, 0x03)]);
+        assert_eq!(
+            device
+                .interfaces()
+                .map(|i| i.num_endpoints())
+                .collect::<Vec<_>>(),
+            [1, 2, 1]
+        );
+
+        assert_eq!(device.device_descriptor().unwrap().usb_version(), 0x0200);
+        assert_eq!(device.configurations().count(), 1);
+
+        assert_eq!(
+            device.sysfs_attr::<String>("removable").unwrap_err().kind(),
+            std::io::ErrorKind::Unsupported
+        );
+    }
+
+    #[test]
+    fn test_device_node_minor() {
+        assert_eq!(device_node_minor(1, 1), Some(0));
+        assert_eq!(device_node_minor(1, 5), Some(4));
+        assert_eq!(device_node_minor(3, 128), Some(383));
+        assert_eq!(device_node_minor(0, 5), None);
+        assert_eq!(device_node_minor(1, 0), None);
+    }
+
+    #[test]
+    fn test_list_bound_drivers() {
+        let fixture = Fixture::load("composite");
+        let enumerator = fixture.enumerator();
+
+        let mut drivers: Vec<_> = enumerator
+            .list_bound_drivers()
+            .unwrap()
+            .map(|(id, intf, driver)| (id.0.addr, intf, driver))
+            .collect();
+        drivers.sort();
+        assert_eq!(
+            drivers,
+            [
+                (3, Some(0), "cdc_acm".to_owned()),
+                (3, Some(1), "cdc_acm".to_owned()),
+            ]
+        );
+
+        let names: HashMap<_, _> = enumerator.list_device_names().unwrap().collect();
+        assert_eq!(names.get("1-2").map(|id| id.addr), Some(3));
+        assert!(!names.contains_key("1-2:1.0"));
+    }
+
+    #[test]
+    fn test_num_endpoints_from_sysfs() {
+        let fixture = Fixture::load("composite");
+        fs::remove_dir_all(
+            fixture.path("sys/devices/pci0000:00/0000:00:14.0/usb1/1-2/1-2:1.0/ep_83"),
+        )
+        .unwrap();
+
+        let devices = fixture.list_devices();
+        let control = devices[0].interfaces().next().unwrap();
+        assert_eq!(control.num_endpoints(), 1);
+        assert_eq!(control.endpoints().count(), 0);
+    }
+
+    #[test]
+    fn test_sysfs_attr_name() {
+        let fixture = Fixture::load("hub");
+        let devices = fixture.list_devices();
+        let hub = &devices[0];
+        let bus = fixture.enumerator().list_buses().unwrap().next().unwrap();
+
+        fn kind<T: std::fmt::Debug>(r: Result<T, std::io::Error>) -> std::io::ErrorKind {
+            r.unwrap_err().kind()
+        }
+
+        assert_eq!(hub.sysfs_attr::<String>("idVendor").unwrap(), "05e3");
+
+        for attr in [
+            "",
+            ".",
+            "..",
+            "../idVendor",
+            "1-1:1.0/bInterfaceClass",
+            "/etc/passwd",
+        ] {
+            assert_eq!(
+                kind(hub.sysfs_attr::<String>(attr)),
+                std::io::ErrorKind::InvalidInput,
+                "{attr:?}"
+            );
+            assert_eq!(
+                kind(hub.set_sysfs_attr(attr, "1")),
+                std::io::ErrorKind::InvalidInput
+            );
+            assert_eq!(
+                kind(bus.sysfs_attr::<String>(attr)),
+                std::io::ErrorKind::InvalidInput
+            );
+            assert_eq!(
+                kind(bus.set_sysfs_attr(attr, "1")),
+                std::io::ErrorKind::InvalidInput
+            );
+        }
+    }
+}
@@ -1,72 +1,172 @@
 use libc::{sockaddr, sockaddr_nl, socklen_t, AF_NETLINK, MSG_DONTWAIT};
-use log::{error, trace, warn};
+use log::{debug, error, trace, warn};
 use rustix::{
     fd::{AsFd, AsRawFd, OwnedFd},
+    fs::inotify,
+    io::Errno,
     net::{netlink, socket_with, AddressFamily, SocketFlags, SocketType},
 };
 use std::{
+    collections::{HashMap, VecDeque},
+    fs,
     io::ErrorKind,
-    mem,
+    mem::{self, MaybeUninit},
     os::{raw::c_void, unix::prelude::BorrowedFd},
     path::Path,
     task::Poll,
 };
 
-use crate::{hotplug::HotplugEvent, Error};
+use crate::{
+    hotplug::{DeviceChange, HotplugEvent},
+    DeviceId, DeviceInfo, Error,
+};
 
-use super::{enumeration::probe_device, events::Async, SysfsPath};
+use super::{
+    enumeration::{interface_number_from_name, probe_device, Enumerator, DEVFS_USB_PREFIX},
+    events::Async,
+    SysfsPath,
+};
 
 const UDEV_MAGIC: &[u8; 12] = b"libudev\0\xfe\xed\xca\xfe";
 const UDEV_MULTICAST_GROUP: u32 = 1 << 1;
 
 pub(crate) struct LinuxHotplugWatch {
-    fd: Async<OwnedFd>,
+    source: Source,
+
+    /// Drivers bound to devices and interfaces, because `unbind` events don't
+    /// say which driver was unbound.
+    drivers: HashMap<(DeviceId, Option<u8>), String>,
+
+    /// Device IDs by sysfs name, because interface uevents don't include
+    /// `BUSNUM` and `DEVNUM`, and the parent device's sysfs directory may
+    /// already be gone when they arrive.
+    device_ids: DeviceIds,
+}
+
+type DeviceIds = HashMap<String, super::DeviceId>;
+
+enum Source {
+    /// udev events from a netlink socket.
+    Netlink(Async<OwnedFd>),
+
+    /// Fallback for when netlink is unavailable, e.g. in a container: watch
+    /// for device nodes created and deleted in `/dev/bus/usb`.
+    Inotify(InotifyWatch),
 }
 
 impl LinuxHotplugWatch {
     pub(crate) fn new() -> Result<Self, Error> {
-        let fd = socket_with(
-            AddressFamily::NETLINK,
-            SocketType::RAW,
-            SocketFlags::CLOEXEC,
-            Some(netlink::KOBJECT_UEVENT),
-        )?;
+        let enumerator = Enumerator::new();
+        let source = match open_netlink() {
+            Ok(fd) => Source::Netlink(Async::new(fd)?),
+            Err(netlink_err) => match InotifyWatch::new(enumerator.clone()) {
+                Ok(watch) => {
+                    debug!("Failed to open udev netlink socket ({netlink_err}), using inotify on {DEVFS_USB_PREFIX}");
+                    Source::Inotify(watch)
+                }
+                Err(e) => {
+                    debug!("Failed to watch {DEVFS_USB_PREFIX} with inotify: {e}");
+                    return Err(netlink_err);
+                }
+            },
+        };
 
-        unsafe {
-            // rustix doesn't support netlink yet (pending https://github.com/bytecodealliance/rustix/pull/1004)
-            // so use libc for now.
-            let mut addr: sockaddr_nl = mem::zeroed();
-            addr.nl_family = AF_NETLINK as u16;
-            addr.nl_groups = UDEV_MULTICAST_GROUP;
-            let r = libc::bind(
-                fd.as_raw_fd(),
-                &addr as *const sockaddr_nl as *const sockaddr,
-                mem::size_of_val(&addr) as socklen_t,
-            );
-            if r != 0 {
-                return Err(Error::last_os_error());
-            }
-        }
+        let drivers = enumerator
+            .list_bound_drivers()
+            .map(|drivers| {
+                drivers
+                    .map(|(id, intf, driver)| ((id, intf), driver))
+                    .collect()
+            })
+            .unwrap_or_else(|e| {
+                warn!("Failed to list bound drivers: {e}");
+                HashMap::new()
+            });
+
+        let device_ids = enumerator
+            .list_device_names()
+            .map(|devices| devices.collect())
+            .unwrap_or_else(|e| {
+                warn!("Failed to list devices: {e}");
+                HashMap::new()
+            });
 
         Ok(LinuxHotplugWatch {
-            fd: Async::new(fd)?,
+            source,
+            drivers,
+            device_ids,
         })
     }
 
     pub(crate) fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<HotplugEvent> {
-        if let Some(event) = try_receive_event(self.fd.inner.as_fd()) {
-            return Poll::Ready(event);
-        }
+        let event = match &mut self.source {
+            Source::Netlink(fd) => {
+                if let Some(event) = try_receive_event(fd.inner.as_fd(), &mut self.device_ids) {
+                    Poll::Ready(event)
+                } else {
+                    if let Err(e) = fd.register(cx.waker()) {
+                        log::error!("failed to register udev socket with epoll: {e}");
+                    }
+                    Poll::Pending
+                }
+            }
+            Source::Inotify(watch) => watch.poll_next(cx),
+        };
 
-        if let Err(e) = self.fd.register(cx.waker()) {
-            log::error!("failed to register udev socket with epoll: {e}");
+        event.map(|mut event| {
+            self.track_driver(&mut event);
+            event
+        })
+    }
+
+    fn track_driver(&mut self, event: &mut HotplugEvent) {
+        match event {
+            HotplugEvent::DriverBound(c) => {
+                if let Some(driver) = &c.driver {
+                    self.drivers
+                        .insert((c.device_id, c.interface_number), driver.clone());
+                }
+            }
+            HotplugEvent::DriverUnbound(c) => {
+                let driver = self.drivers.remove(&(c.device_id, c.interface_number));
+                c.driver = c.driver.take().or(driver);
+            }
+            HotplugEvent::Disconnected(id, _) => {
+                self.drivers.retain(|(d, _), _| d != id);
+            }
+            _ => {}
         }
+    }
+}
 
-        Poll::Pending
+fn open_netlink() -> Result<OwnedFd, Error> {
+    let fd = socket_with(
+        AddressFamily::NETLINK,
+        SocketType::RAW,
+        SocketFlags::CLOEXEC,
+        Some(netlink::KOBJECT_UEVENT),
+    )?;
+
+    unsafe {
+        // rustix doesn't support netlink yet (pending https://github.com/bytecodealliance/rustix/pull/1004)
+        // so use libc for now.
+        let mut addr: sockaddr_nl = mem::zeroed();
+        addr.nl_family = AF_NETLINK as u16;
+        addr.nl_groups = UDEV_MULTICAST_GROUP;
+        let r = libc::bind(
+            fd.as_raw_fd(),
+            &addr as *const sockaddr_nl as *const sockaddr,
+            mem::size_of_val(&addr) as socklen_t,
+        );
+        if r != 0 {
+            return Err(Error::last_os_error());
+        }
     }
+
+    Ok(fd)
 }
 
-fn try_receive_event(fd: BorrowedFd) -> Option<HotplugEvent> {
+fn try_receive_event(fd: BorrowedFd, device_ids: &mut DeviceIds) -> Option<HotplugEvent> {
     let mut buf = [0; 8192];
 
     let received = unsafe {
@@ -90,7 +190,9 @@ fn try_receive_event(fd: BorrowedFd) -> Option<HotplugEvent> {
     match received {
         // udev messages will normally be sent to a multicast group, which only
         // root can send to. Reject unicast messages that may be from anywhere.
-        Ok((size, groups)) if groups == UDEV_MULTICAST_GROUP => parse_packet(&buf[..size]),
+        Ok((size, groups)) if groups == UDEV_MULTICAST_GROUP => {
+            parse_packet(&buf[..size], device_ids)
+        }
         Ok((_, src)) => {
             warn!("udev netlink socket received message from {src:?}");
             None
@@ -103,7 +205,7 @@ fn try_receive_event(fd: BorrowedFd) -> Option<HotplugEvent> {
     }
 }
 
-fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
+fn parse_packet(buf: &[u8], device_ids: &mut DeviceIds) -> Option<HotplugEvent> {
     if buf.len() < 24 {
         error!("packet too short: {buf:x?}");
         return None;
@@ -121,23 +223,27 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
         return None;
     };
 
-    let mut is_add = None;
+    let mut action = None;
+    let mut is_interface = None;
     let mut busnum = None;
     let mut devnum = None;
     let mut devpath = None;
+    let mut driver = None;
 
     for (k, v) in parse_properties(properties_buf) {
         trace!("uevent property {k} = {v}");
         match k {
             "SUBSYSTEM" if v != "usb" => return None,
-            "DEVTYPE" if v != "usb_device" => return None,
-            "ACTION" => {
-                is_add = Some(match v {
-                    "add" => true,
-                    "remove" => false,
+            "DEVTYPE" => {
+                is_interface = Some(match v {
+                    "usb_device" => false,
+                    "usb_interface" => true,
                     _ => return None,
                 });
             }
+            "ACTION" => {
+                action = Some(v);
+            }
             "BUSNUM" => {
                 busnum = v.parse::<u8>().ok();
             }
@@ -147,31 +253,246 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
             "DEVPATH" => {
                 devpath = Some(v);
             }
+            "DRIVER" => {
+                driver = Some(v);
+            }
             _ => {}
         }
     }
 
-    let is_add = is_add?;
-    let busnum = busnum?;
-    let devnum = devnum?;
+    let action = action?;
+    let is_interface = is_interface?;
     let devpath = devpath?;
+    let path = Path::new("/sys/").join(devpath.trim_start_matches('/'));
+
+    let (id, interface_number) = if is_interface {
+        if !matches!(action, "bind" | "unbind" | "change") {
+            return None;
+        }
+
+        // Interface uevents don't include BUSNUM and DEVNUM, so look up the
+        // parent device by name, or read them from its sysfs directory if it
+        // hasn't been seen yet.
+        let interface_number = interface_number_from_name(path.file_name()?.to_str()?)?;
+        let parent = path.parent()?;
+        let cached = parent
+            .file_name()
+            .and_then(|name| device_ids.get(name.to_str()?));
+        let id = match cached {
+            Some(&id) => id,
+            None => {
+                let parent = SysfsPath(parent.to_owned());
+                match (parent.read_attr("busnum"), parent.read_attr("devnum")) {
+                    (Ok(bus), Ok(addr)) => super::DeviceId { bus, addr },
+                    (Err(e), _) | (_, Err(e)) => {
+                        debug!("Ignoring {action} event for interface {path:?}: {e}");
+                        return None;
+                    }
+                }
+            }
+        };
+        (id, Some(interface_number))
+    } else {
+        let id = super::DeviceId {
+            bus: busnum?,
+            addr: devnum?,
+        };
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            if action == "remove" {
+                device_ids.remove(name);
+            } else {
+                device_ids.insert(name.to_owned(), id);
+            }
+        }
+        (id, None)
+    };
+
+    let change = || DeviceChange {
+        device_id: crate::DeviceId(id),
+        interface_number,
+        driver: driver.map(str::to_owned),
+    };
 
-    if is_add {
-        let path = Path::new("/sys/").join(devpath.trim_start_matches('/'));
-        match probe_device(SysfsPath(path.clone())) {
+    match action {
+        "add" => match probe_device(SysfsPath(path.clone())) {
             Ok(d) => Some(HotplugEvent::Connected(d)),
             Err(e) => {
                 warn!("Failed to probe device {path:?}: {e}");
                 None
             }
+        },
+        "remove" => Some(HotplugEvent::Disconnected(crate::DeviceId(id), None)),
+        "bind" => Some(HotplugEvent::DriverBound(change())),
+        "unbind" => Some(HotplugEvent::DriverUnbound(change())),
+        "change" => Some(HotplugEvent::Changed(change())),
+        _ => None,
+    }
+}
+
+/// Hotplug events derived from device nodes appearing and disappearing in
+/// `/dev/bus/usb/BBB/DDD`, with device details probed from sysfs.
+///
+/// Unlike udev events, this can't report driver binding or changes.
+struct InotifyWatch {
+    fd: Async<OwnedFd>,
+
+    /// Where to find the device nodes and probe devices.
+    enumerator: Enumerator,
+
+    /// Watch descriptor for `/dev/bus/usb` itself, to pick up new buses.
+    root_wd: i32,
+
+    /// Watch descriptors for each bus directory, mapped to the bus number.
+    buses: HashMap<i32, u8>,
+
+    /// Events read from the inotify fd but not yet returned.
+    pending: VecDeque<HotplugEvent>,
+}
+
+impl InotifyWatch {
+    fn new(enumerator: Enumerator) -> Result<InotifyWatch, Error> {
+        let devfs_usb = enumerator.devfs_usb();
+        let fd = inotify::init(inotify::CreateFlags::CLOEXEC | inotify::CreateFlags::NONBLOCK)?;
+        let root_wd = inotify::add_watch(
+            &fd,
+            &devfs_usb,
+            inotify::WatchFlags::CREATE | inotify::WatchFlags::ONLYDIR,
+        )?;
+
+        let mut watch = InotifyWatch {
+            fd: Async::new(fd)?,
+            enumerator,
+            root_wd,
+            buses: HashMap::new(),
+            pending: VecDeque::new(),
+        };
+
+        for entry in fs::read_dir(devfs_usb)? {
+            if let Some(name) = entry?.file_name().to_str() {
+                watch.add_bus(name);
+            }
         }
-    } else {
-        Some(HotplugEvent::Disconnected(crate::DeviceId(
-            super::DeviceId {
-                bus: busnum,
-                addr: devnum,
-            },
-        )))
+
+        Ok(watch)
+    }
+
+    fn add_bus(&mut self, name: &str) {
+        let Ok(busnum @ 1..) = name.parse::<u8>() else {
+            return;
+        };
+
+        let path = self.enumerator.devfs_usb().join(name);
+        match inotify::add_watch(
+            &self.fd.inner,
+            &path,
+            inotify::WatchFlags::CREATE
+                | inotify::WatchFlags::DELETE
+                | inotify::WatchFlags::ONLYDIR,
+        ) {
+            Ok(wd) => {
+                self.buses.insert(wd, busnum);
+            }
+            Err(e) => warn!("Failed to watch {path:?} with inotify: {e}"),
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<HotplugEvent> {
+        if let Some(event) = self.pending.pop_front() {
+            return Poll::Ready(event);
+        }
+
+        let mut buf = [MaybeUninit::uninit(); 4096];
+        let mut new_buses = Vec::new();
+        let mut reader = inotify::Reader::new(&self.fd.inner, &mut buf);
+
+        loop {
+            let event = match reader.next() {
+                Ok(event) => event,
+                Err(Errno::AGAIN) => break,
+                Err(e) => {
+                    error!("failed to read inotify events: {e}");
+                    break;
+                }
+            };
+
+            let flags = event.events();
+            let Some(name) = event.file_name().and_then(|n| n.to_str().ok()) else {
+                if flags.contains(inotify::ReadFlags::IGNORED) {
+                    // Bus directory removed
+                    self.buses.remove(&event.wd());
+                }
+                continue;
+            };
+
+            if event.wd() == self.root_wd {
+                if flags.contains(inotify::ReadFlags::CREATE) {
+                    new_buses.push(name.to_owned());
+                }
+                continue;
+            }
+
+            let (Some(&bus), Ok(addr @ 1..)) = (self.buses.get(&event.wd()), name.parse::<u8>())
+            else {
+                continue;
+            };
+
+            let id = super::DeviceId { bus, addr };
+            if flags.contains(inotify::ReadFlags::CREATE) {
+                if let Some(device) = self.probe_device_node(id) {
+                    self.pending.push_back(HotplugEvent::Connected(device));
+                }
+            } else if flags.contains(inotify::ReadFlags::DELETE) {
+                self.pending
+                    .push_back(HotplugEvent::Disconnected(DeviceId(id), None));
+            }
+        }
+
+        for name in new_buses {
+            self.add_bus(&name);
+            // Devices on the new bus may have been created before the watch
+            // was added.
+            for entry in fs::read_dir(self.enumerator.devfs_usb().join(&name))
+                .into_iter()
+                .flatten()
+                .flatten()
+            {
+                let (Ok(bus), Some(Ok(addr))) = (
+                    name.parse::<u8>(),
+                    entry.file_name().to_str().map(str::parse::<u8>),
+                ) else {
+                    continue;
+                };
+                if let Some(device) = self.probe_device_node(super::DeviceId { bus, addr }) {
+                    self.pending.push_back(HotplugEvent::Connected(device));
+                }
+            }
+        }
+
+        if let Some(event) = self.pending.pop_front() {
+            return Poll::Ready(event);
+        }
+
+        if let Err(e) = self.fd.register(cx.waker()) {
+            log::error!("failed to register inotify fd with epoll: {e}");
+        }
+
+        Poll::Pending
+    }
+}
+
+impl InotifyWatch {
+    /// Probe the device for a usbfs device node, ignoring names that can't
+    /// be device nodes, such as address 0.
+    fn probe_device_node(&self, id: super::DeviceId) -> Option<DeviceInfo> {
+        if id.bus == 0 || id.addr == 0 {
+            debug!("Ignoring invalid device node {id:?}");
+            return None;
+        }
+
+        self.enumerator
+            .probe_usbfs_device(id.bus, id.addr)
+            .inspect_err(|e| warn!("Failed to probe device {id:?}: {e}"))
+            .ok()
     }
 }
 
@@ -180,3 +501,185 @@ fn parse_properties(buf: &[u8]) -> impl Iterator<Item = (&str, &str)> + '_ {
     buf.split(|b| b == &0)
         .filter_map(|entry| std::str::from_utf8(entry).ok()?.split_once('='))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs,
+        sync::Arc,
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use super::{parse_packet, DeviceIds, InotifyWatch, UDEV_MAGIC};
+    use crate::{
+        hotplug::HotplugEvent,
+        platform::{linux_usbfs::enumeration::tests::Fixture, DeviceId},
+    };
+
+    fn packet(properties: &[&str]) -> Vec<u8> {
+        let mut buf = UDEV_MAGIC.to_vec();
+        buf.resize(40, 0);
+        let properties = properties.join("\0");
+        buf[16..20].copy_from_slice(&40u32.to_ne_bytes());
+        buf[20..24].copy_from_slice(&(properties.len() as u32).to_ne_bytes());
+        buf.extend_from_slice(properties.as_bytes());
+        buf
+    }
+
+    #[test]
+    fn test_parse_driver_events() {
+        let mut device_ids = DeviceIds::new();
+        let bind = packet(&[
+            "ACTION=bind",
+            "DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1/1-2",
+            "SUBSYSTEM=usb",
+            "DEVTYPE=usb_device",
+            "DRIVER=usb",
+            "BUSNUM=001",
+            "DEVNUM=005",
+        ]);
+        let Some(HotplugEvent::DriverBound(c)) = parse_packet(&bind, &mut device_ids) else {
+            panic!("expected DriverBound");
+        };
+        assert_eq!(c.device_id().0.bus, 1);
+        assert_eq!(c.device_id().0.addr, 5);
+        assert_eq!(c.interface_number(), None);
+        assert_eq!(c.driver(), Some("usb"));
+
+        let remove = packet(&[
+            "ACTION=remove",
+            "DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1/1-2",
+            "SUBSYSTEM=usb",
+            "DEVTYPE=usb_device",
+            "BUSNUM=001",
+            "DEVNUM=005",
+        ]);
+        assert!(matches!(
+            parse_packet(&remove, &mut device_ids),
+            Some(HotplugEvent::Disconnected(..))
+        ));
+
+        let other = packet(&[
+            "ACTION=bind",
+            "DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1/1-2/1-2:1.0/0003:1234:5678.0001",
+            "SUBSYSTEM=hid",
+            "DRIVER=hid-generic",
+        ]);
+        assert!(parse_packet(&other, &mut device_ids).is_none());
+    }
+
+    #[test]
+    fn test_parse_interface_events() {
+        // The parent device's sysfs directory doesn't exist, as when the
+        // device is being removed, so its ID must come from earlier events.
+        let mut device_ids = HashMap::from([(
+            "9-1".to_owned(),
+            crate::platform::DeviceId { bus: 9, addr: 3 },
+        )]);
+
+        let unbind = packet(&[
+            "ACTION=unbind",
+            "DEVPATH=/devices/nonexistent/usb9/9-1/9-1:1.2",
+            "SUBSYSTEM=usb",
+            "DEVTYPE=usb_interface",
+        ]);
+        let Some(HotplugEvent::DriverUnbound(c)) = parse_packet(&unbind, &mut device_ids) else {
+            panic!("expected DriverUnbound");
+        };
+        assert_eq!(c.device_id().0.bus, 9);
+        assert_eq!(c.device_id().0.addr, 3);
+        assert_eq!(c.interface_number(), Some(2));
+
+        // A device event for a new device makes its interfaces resolvable.
+        let bind = packet(&[
+            "ACTION=bind",
+            "DEVPATH=/devices/nonexistent/usb9/9-2",
+            "SUBSYSTEM=usb",
+            "DEVTYPE=usb_device",
+            "DRIVER=usb",
+            "BUSNUM=009",
+            "DEVNUM=004",
+        ]);
+        assert!(parse_packet(&bind, &mut device_ids).is_some());
+
+        let bind_interface = packet(&[
+            "ACTION=bind",
+            "DEVPATH=/devices/nonexistent/usb9/9-2/9-2:1.0",
+            "SUBSYSTEM=usb",
+            "DEVTYPE=usb_interface",
+            "DRIVER=usbhid",
+        ]);
+        let Some(HotplugEvent::DriverBound(c)) = parse_packet(&bind_interface, &mut device_ids)
+        else {
+            panic!("expected DriverBound");
+        };
+        assert_eq!(c.device_id().0.addr, 4);
+        assert_eq!(c.interface_number(), Some(0));
+        assert_eq!(c.driver(), Some("usbhid"));
+
+        // Once the device is removed, its interfaces are unknown.
+        let remove = packet(&[
+            "ACTION=remove",
+            "DEVPATH=/devices/nonexistent/usb9/9-1",
+            "SUBSYSTEM=usb",
+            "DEVTYPE=usb_device",
+            "BUSNUM=009",
+            "DEVNUM=003",
+        ]);
+        assert!(parse_packet(&remove, &mut device_ids).is_some());
+        assert!(parse_packet(&unbind, &mut device_ids).is_none());
+    }
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn test_inotify_watch() {
+        let fixture = Fixture::load("usbfs");
+        let bus = fixture.path("dev/bus/usb/001");
+        let node = fs::read(bus.join("003")).unwrap();
+
+        let mut watch = InotifyWatch::new(fixture.enumerator()).unwrap();
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        assert!(watch.poll_next(&mut cx).is_pending());
+
+        // Address 0 is never assigned, so isn't a device node.
+        fs::write(bus.join("000"), &node).unwrap();
+        fs::write(bus.join("007"), &node).unwrap();
+        let Poll::Ready(HotplugEvent::Connected(device)) = watch.poll_next(&mut cx) else {
+            panic!("expected Connected");
+        };
+        assert_eq!(device.id().0, DeviceId { bus: 1, addr: 7 });
+        assert_eq!((device.vendor_id(), device.product_id()), (0x1209, 0x0010));
+        assert!(watch.poll_next(&mut cx).is_pending());
+
+        fs::remove_file(bus.join("000")).unwrap();
+        fs::remove_file(bus.join("007")).unwrap();
+        let Poll::Ready(HotplugEvent::Disconnected(id, None)) = watch.poll_next(&mut cx) else {
+            panic!("expected Disconnected");
+        };
+        assert_eq!(id.0, DeviceId { bus: 1, addr: 7 });
+        assert!(watch.poll_next(&mut cx).is_pending());
+
+        // Nodes created on a new bus before its watch is added are found by
+        // scanning it.
+        let new_bus = fixture.path("dev/bus/usb/002");
+        fs::create_dir(&new_bus).unwrap();
+        fs::write(new_bus.join("004"), &node).unwrap();
+        let Poll::Ready(HotplugEvent::Connected(device)) = watch.poll_next(&mut cx) else {
+            panic!("expected Connected");
+        };
+        assert_eq!(device.id().0, DeviceId { bus: 2, addr: 4 });
+
+        fs::remove_file(new_bus.join("004")).unwrap();
+        let Poll::Ready(HotplugEvent::Disconnected(id, None)) = watch.poll_next(&mut cx) else {
+            panic!("expected Disconnected");
+        };
+        assert_eq!(id.0, DeviceId { bus: 2, addr: 4 });
+    }
+}
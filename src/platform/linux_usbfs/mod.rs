@@ -5,7 +5,10 @@ mod usbfs;
 
 mod enumeration;
 mod events;
-pub use enumeration::{list_buses, list_devices, SysfsPath};
+pub(crate) use enumeration::check_attr_name;
+#[cfg(test)]
+pub(crate) use enumeration::tests::device_info;
+pub use enumeration::{list_buses, list_devices, list_ports, probe_port, Enumerator, SysfsPath};
 
 mod device;
 pub(crate) use device::LinuxDevice as Device;
@@ -17,6 +20,7 @@ pub(crate) use hotplug::LinuxHotplugWatch as HotplugWatch;
 use crate::transfer::TransferError;
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceId {
     pub(crate) bus: u8,
     pub(crate) addr: u8,
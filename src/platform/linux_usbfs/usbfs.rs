@@ -46,17 +46,50 @@ struct DetachAndClaim {
     driver: [c_uchar; 255 + 1],
 }
 
-pub fn detach_and_claim_interface<Fd: AsFd>(fd: Fd, interface: u8) -> io::Result<()> {
-    const USBDEVFS_DISCONNECT_CLAIM_EXCEPT_DRIVER: c_uint = 0x02;
-    unsafe {
+const USBDEVFS_DISCONNECT_CLAIM_IF_DRIVER: c_uint = 0x01;
+const USBDEVFS_DISCONNECT_CLAIM_EXCEPT_DRIVER: c_uint = 0x02;
+
+impl DetachAndClaim {
+    /// Fails with `EINVAL` if `driver` contains a nul byte or doesn't fit in
+    /// the kernel's buffer.
+    fn new(interface: u8, flags: c_uint, driver: &[u8]) -> io::Result<DetachAndClaim> {
         let mut dc = DetachAndClaim {
             interface: interface.into(),
-            flags: USBDEVFS_DISCONNECT_CLAIM_EXCEPT_DRIVER,
+            flags,
             driver: [0; 256],
         };
 
-        dc.driver[0..6].copy_from_slice(b"usbfs\0");
+        if driver.len() >= dc.driver.len() || driver.contains(&0) {
+            return Err(io::Errno::INVAL);
+        }
+        dc.driver[..driver.len()].copy_from_slice(driver);
+        Ok(dc)
+    }
+}
 
+pub fn detach_and_claim_interface<Fd: AsFd>(fd: Fd, interface: u8) -> io::Result<()> {
+    let dc = DetachAndClaim::new(interface, USBDEVFS_DISCONNECT_CLAIM_EXCEPT_DRIVER, b"usbfs")?;
+    unsafe {
+        let ctl = ioctl::Setter::<opcodes::USBDEVFS_DISCONNECT_CLAIM, DetachAndClaim>::new(dc);
+
+        ioctl::ioctl(&fd, ctl)
+    }
+}
+
+/// Detach the kernel driver from an interface and claim it, only if the
+/// driver is `driver`, in a single operation. Fails with `EBUSY` if a
+/// different driver is bound.
+pub fn detach_driver_and_claim_interface<Fd: AsFd>(
+    fd: Fd,
+    interface: u8,
+    driver: &str,
+) -> io::Result<()> {
+    let dc = DetachAndClaim::new(
+        interface,
+        USBDEVFS_DISCONNECT_CLAIM_IF_DRIVER,
+        driver.as_bytes(),
+    )?;
+    unsafe {
         let ctl = ioctl::Setter::<opcodes::USBDEVFS_DISCONNECT_CLAIM, DetachAndClaim>::new(dc);
 
         ioctl::ioctl(&fd, ctl)
@@ -117,6 +150,45 @@ pub fn attach_kernel_driver<Fd: AsFd>(fd: Fd, interface: u8) -> io::Result<()> {
     }
 }
 
+#[repr(C)]
+struct GetDriver {
+    interface: c_uint,
+    driver: [c_uchar; 255 + 1],
+}
+
+/// Get the name of the driver bound to an interface, failing with `ENODATA`
+/// if there is none.
+pub fn get_driver<Fd: AsFd>(fd: Fd, interface: u8) -> io::Result<Vec<u8>> {
+    let mut gd = GetDriver {
+        interface: interface.into(),
+        driver: [0; 256],
+    };
+    unsafe {
+        let ctl = ioctl::Updater::<ioctl::WriteOpcode<b'U', 8, GetDriver>, GetDriver>::new(&mut gd);
+        ioctl::ioctl(fd, ctl)?;
+    }
+    let len = gd.driver.iter().position(|&c| c == 0).unwrap_or(255);
+    Ok(gd.driver[..len].to_vec())
+}
+
+#[repr(C)]
+pub struct ConnInfoEx {
+    pub size: u32,
+    pub busnum: u32,
+    pub devnum: u32,
+    pub speed: u32,
+    pub num_ports: u8,
+    pub ports: [u8; 7],
+}
+
+/// Get the bus, address, speed, and port chain of the device (Linux 5.2+).
+pub fn get_conninfo_ex<Fd: AsFd>(fd: Fd) -> io::Result<ConnInfoEx> {
+    unsafe {
+        let ctl = ioctl::Getter::<ioctl::ReadOpcode<b'U', 32, ConnInfoEx>, ConnInfoEx>::new();
+        ioctl::ioctl(fd, ctl)
+    }
+}
+
 #[repr(C)]
 struct SetAltSetting {
     interface: c_int,
@@ -286,3 +358,22 @@ pub fn clear_halt<Fd: AsFd>(fd: Fd, endpoint: u8) -> io::Result<()> {
         ioctl::ioctl(fd, ctl)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DetachAndClaim, USBDEVFS_DISCONNECT_CLAIM_IF_DRIVER};
+
+    #[test]
+    fn test_detach_and_claim_driver() {
+        let dc = DetachAndClaim::new(2, USBDEVFS_DISCONNECT_CLAIM_IF_DRIVER, b"usbhid").unwrap();
+        assert_eq!(dc.interface, 2);
+        assert_eq!(dc.flags, USBDEVFS_DISCONNECT_CLAIM_IF_DRIVER);
+        assert_eq!(&dc.driver[..7], b"usbhid\0");
+        assert!(dc.driver[7..].iter().all(|&b| b == 0));
+
+        // The name must leave room for the nul terminator.
+        assert!(DetachAndClaim::new(0, 0, &[b'a'; 255]).is_ok());
+        assert!(DetachAndClaim::new(0, 0, &[b'a'; 256]).is_err());
+        assert!(DetachAndClaim::new(0, 0, b"usb\0hid").is_err());
+    }
+}